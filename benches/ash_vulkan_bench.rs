@@ -1,7 +1,6 @@
 use adel::renderer;
 use adel::window::WinitWindow;
 use criterion::{black_box, criterion_group, criterion_main, Criterion};
-use std::sync::mpsc;
 
 pub fn window_benchmark(c: &mut Criterion) {
     c.bench_function("adel_window", |b| b.iter(|| WinitWindow::new()));
@@ -11,10 +10,7 @@ pub fn vulkan_benchmark(c: &mut Criterion) {
         b.iter(|| {
             let mut winit_window = WinitWindow::new();
             let window = winit_window.window().unwrap();
-            // TODO: Tmp Values to send data to System without creating unique functions
-            let (tx, rx): (mpsc::Sender<(u32, u32)>, mpsc::Receiver<(u32, u32)>) = mpsc::channel();
-
-            let renderer_ash = renderer::RendererAsh::new(&window, rx).unwrap();
+            let renderer_ash = renderer::RendererAsh::new(&window).unwrap();
             std::process::exit(0);
         })
     });
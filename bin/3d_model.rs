@@ -1,11 +1,13 @@
 use adel::app::Application;
+use adel::camera::FlyCamera;
 use adel::ecs::World;
-use adel::input::KeyboardComponent;
+use adel::input::{InputMap, InputPlayback, InputRecorder, KeyboardComponent};
 use adel::renderer::utility::model::ModelComponent;
 use adel::renderer::TransformComponent;
 use nalgebra::Vector3;
 use std::path::Path;
-fn main() {
+use std::process::ExitCode;
+fn main() -> ExitCode {
     simple_logger::SimpleLogger::new().env().init().unwrap();
     let mut world = World::new();
     let floor = ModelComponent::builder().load_model(Path::new("resources/quad.obj"));
@@ -46,6 +48,21 @@ fn main() {
 
     world.add_component_to_entity(camera_entity, camera_transform);
     world.add_component_to_entity(camera_entity, keyboard_component);
-    let app = Application::new(world);
-    app.main_loop();
+    world.add_component_to_entity(camera_entity, FlyCamera::new());
+    world.insert_resource(InputMap::load(Path::new("resources/input_bindings.txt")).unwrap());
+    // --record <file> writes the session's input to file, --replay <file> flies the camera along it again
+    let args: Vec<String> = std::env::args().collect();
+    let arg = |flag: &str| {
+        args.iter()
+            .position(|arg| arg == flag)
+            .and_then(|index| args.get(index + 1))
+    };
+    if let Some(path) = arg("--replay") {
+        world.insert_resource(InputPlayback::load(Path::new(path)).unwrap());
+    }
+    let mut app = Application::new(world);
+    if let Some(path) = arg("--record") {
+        app.add_system(InputRecorder::new(path));
+    }
+    app.main_loop().into()
 }
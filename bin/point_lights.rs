@@ -1,5 +1,6 @@
 use adel::app::Application;
-use adel::ecs::World;
+use adel::camera::OrbitCamera;
+use adel::ecs::{Entity, World};
 use adel::input::KeyboardComponent;
 use adel::renderer::definitions::{vec3_to_vec4, PointLightComponent};
 use adel::renderer::utility::model::ModelComponent;
@@ -7,16 +8,17 @@ use adel::renderer::TransformComponent;
 use nalgebra::{Translation, Vector3, Vector4};
 use nalgebra_glm as glm;
 use std::path::Path;
-fn main() {
+use std::process::ExitCode;
+fn main() -> ExitCode {
     simple_logger::SimpleLogger::new().env().init().unwrap();
     let mut world = World::new();
     let mut entity_vector = load_models(&mut world);
 
     load_point_lights(&mut world, &mut entity_vector);
     let app = Application::new(world);
-    app.main_loop();
+    app.main_loop().into()
 }
-fn load_point_lights(world: &mut World, entity_vector: &mut Vec<usize>) {
+fn load_point_lights(world: &mut World, entity_vector: &mut Vec<Entity>) {
     let light_colors: Vec<Vector4<f32>> = vec![
         Vector4::new(1.0, 0.1, 0.1, 1.0),
         Vector4::new(0.1, 0.1, 1.0, 1.0),
@@ -50,7 +52,7 @@ fn load_point_lights(world: &mut World, entity_vector: &mut Vec<usize>) {
         entity_vector.push(point_light_entity);
     }
 }
-fn load_models(world: &mut World) -> Vec<usize> {
+fn load_models(world: &mut World) -> Vec<Entity> {
     let floor = ModelComponent::builder().load_model(Path::new("resources/quad.obj"));
     let mut floor_transform = TransformComponent::new(
         Vector3::new(0.0, 0.5, 0.0),
@@ -93,5 +95,7 @@ fn load_models(world: &mut World) -> Vec<usize> {
 
     world.add_component_to_entity(camera_entity, camera_transform);
     world.add_component_to_entity(camera_entity, keyboard_component);
+    // Turntable around the lights, drag to orbit and scroll to zoom
+    world.add_component_to_entity(camera_entity, OrbitCamera::new(Vector3::zeros()));
     entity_vector
 }
@@ -1,101 +1,362 @@
-use crate::adel_camera::Camera;
+use crate::adel_app::{
+    AppBuilder, CorePlugin, DefaultPlugins, FixedTimestep, FrameLimiter, LoopMode,
+};
 use crate::adel_ecs::World;
-use crate::adel_ecs::{RunStage, System};
-use crate::adel_input::{InputConsumer, KeyboardHandler};
-use crate::adel_renderer::RendererAsh;
-use crate::adel_winit::WinitWindow;
-use std::collections::HashMap;
-use std::collections::HashSet;
-use std::sync::mpsc;
+use crate::adel_ecs::{
+    EventReader, Events, IntoSystemDescriptor, Profiler, RunStage, Schedule, ScheduleError,
+    SystemErrors,
+};
+use crate::adel_input::{InputConsumer, InputPlayback, InputPlugin, TextInput};
+use crate::adel_renderer::store_previous_transforms;
+use crate::adel_time::Time;
+use crate::adel_winit::{KeyboardEvent, WindowCloseRequested, WindowFocused, WindowResized};
+use std::cell::RefMut;
 use std::time;
 use winit::{
-    event::{Event, WindowEvent},
+    event::{DeviceEvent, Event, WindowEvent},
     event_loop::{ControlFlow, EventLoop},
+    platform::run_return::EventLoopExtRunReturn,
 };
 
+// Sent by any system to stop the Application at the end of the frame, windowed or headless
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct AppExit;
+
+// How an Application ended, returned once every system's shutdown has run
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum ExitStatus {
+    // AppExit was sent, the window was closed or the caller called exit
+    Success,
+    // A system failed with ErrorPolicy::Shutdown, the errors are in the SystemErrors resource
+    Failure,
+}
+
+impl ExitStatus {
+    pub fn is_success(&self) -> bool {
+        *self == ExitStatus::Success
+    }
+    // Process exit code, 0 on success
+    pub fn code(&self) -> i32 {
+        match self {
+            ExitStatus::Success => 0,
+            ExitStatus::Failure => 1,
+        }
+    }
+}
+
+impl From<ExitStatus> for std::process::ExitCode {
+    fn from(status: ExitStatus) -> Self {
+        std::process::ExitCode::from(status.code() as u8)
+    }
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+enum LoopState {
+    NotStarted,
+    Running,
+    Exited(ExitStatus),
+}
+
 pub struct Application {
     pub world: World,
-    pub systems: HashMap<String, Box<dyn System>>,
-    event_loop: EventLoop<()>,
-    transmitter: mpsc::Sender<(u32, u32)>,
+    pub schedule: Schedule,
+    // None for headless Applications
+    event_loop: Option<EventLoop<()>>,
+    state: LoopState,
+    // Start of the last frame, for measuring frame times
+    current_time: time::Instant,
+    app_exit_reader: EventReader<AppExit>,
 }
 
 impl Application {
-    // Application is steadily growing with all of the initialization other systems need to implement
-    // Perhaps make it a trait of systems to have an init function that can run
-    pub fn new(mut world: World) -> Self {
-        // WinitWindow handles boiler plate setup for windowing/event_loop
-        let mut winit_window = WinitWindow::new();
-        let event_loop: EventLoop<()> = winit_window.event_loop().unwrap();
-        let window = winit_window.window().unwrap();
-        // TODO: Tmp Values to send data to System without creating unique functions
-        let (tx, rx): (mpsc::Sender<(u32, u32)>, mpsc::Receiver<(u32, u32)>) = mpsc::channel();
-
-        let renderer_ash = RendererAsh::new(&window, rx).unwrap();
-        world.insert_resource(window);
-
-        // Create the input Consumer and keyboard handler
-        let keyboard_handler = KeyboardHandler::new();
-        let input_consumer = InputConsumer {
-            pressed: HashSet::new(),
-        };
-        let camera = Camera::new();
-
-        world.insert_resource::<InputConsumer>(input_consumer);
-        world.insert_resource::<Camera>(camera);
-        //log::info!("What is the value {:?}", keyboard.pressed);
-        let mut systems: HashMap<String, Box<dyn System>> = HashMap::new();
-        systems.insert(
-            keyboard_handler.name().to_owned(),
-            Box::new(keyboard_handler),
-        );
-        systems.insert(renderer_ash.name().to_owned(), Box::new(renderer_ash));
-        systems.insert(winit_window.name().to_owned(), Box::new(winit_window));
+    // Shorthand for the DefaultPlugins, use Application::builder to pick plugins
+    pub fn new(world: World) -> Self {
+        let mut builder = Application::builder(world);
+        builder.add_plugins(DefaultPlugins::build());
+        let app = builder.build();
         log::info!("Finished Creating app");
+        app
+    }
+    pub fn builder(world: World) -> AppBuilder {
+        AppBuilder::new(world)
+    }
+    // No window, event loop or renderer. Only the CorePlugin and InputPlugin are added, so the TimerSystem,
+    // InputSourceSystem and QuitOnAction run next to the systems added with add_system. Meant for tests and
+    // servers, driven by run_headless or run_frame instead of main_loop
+    pub fn headless(world: World) -> Self {
+        let mut builder = Application::builder(world);
+        builder.add_plugin(CorePlugin).add_plugin(InputPlugin);
+        builder.build()
+    }
+    pub(crate) fn from_parts(
+        world: World,
+        schedule: Schedule,
+        event_loop: Option<EventLoop<()>>,
+    ) -> Self {
         Self {
             world,
-            systems,
+            schedule,
             event_loop,
-            transmitter: tx,
+            state: LoopState::NotStarted,
+            current_time: time::Instant::now(),
+            app_exit_reader: EventReader::new(),
+        }
+    }
+
+    // Systems added after new, before/after constraints can point at any label in the same RunStage. Systems added
+    // between frames are started up at the start of the next one
+    pub fn add_system(&mut self, system: impl IntoSystemDescriptor) {
+        self.schedule.add_system(system);
+    }
+
+    // Runs startup, then frames of dt seconds until max_frames have run (forever if None) or a system sends
+    // AppExit, then shutdown. RedrawUpdate is skipped since there is nothing to draw to. The World is handed
+    // back so the caller can look at what the systems did
+    pub fn run_headless(
+        mut self,
+        dt: f32,
+        max_frames: Option<u32>,
+    ) -> Result<World, ScheduleError> {
+        let mut frame = 0;
+        while max_frames.map_or(true, |max_frames| frame < max_frames) {
+            if self.advance(Some(dt))?.is_some() {
+                break;
+            }
+            frame += 1;
+        }
+        self.exit();
+        // Application shuts down on drop, so the World can only be swapped out
+        Ok(std::mem::replace(&mut self.world, World::new()))
+    }
+
+    // Runs until a system sends AppExit, the window is closed or a system fails with ErrorPolicy::Shutdown
+    // A schedule that can't be built is logged and counts as a Failure
+    pub fn main_loop(mut self) -> ExitStatus {
+        loop {
+            match self.run_frame() {
+                Ok(Some(status)) => return status,
+                Ok(None) => {}
+                Err(error) => {
+                    log::error!("Failed to build the system schedule: {}", error);
+                    return ExitStatus::Failure;
+                }
+            }
         }
     }
 
-    pub fn main_loop(mut self) {
-        #[allow(unused_imports)]
-        use winit::event::{ElementState, KeyboardInput, VirtualKeyCode};
-        let mut current_time = time::Instant::now();
-        // Initialize everything that needs to at startup
-        {
-            for i in &mut self.systems.values_mut() {
-                i.as_mut().startup(&mut self.world);
+    // Runs a single frame, starting the Application up first if this is the first one. Window events that came
+    // in since the last frame are handled before the systems run. Returns the exit status once the Application
+    // has exited and shut every system down, every later call just returns it again. Fails without starting
+    // anything up if the labels or before/after constraints of the systems don't make a valid schedule
+    pub fn run_frame(&mut self) -> Result<Option<ExitStatus>, ScheduleError> {
+        self.advance(None)
+    }
+
+    // Runs up to frames frames, stopping early if the Application exits
+    pub fn run_frames(&mut self, frames: u32) -> Result<Option<ExitStatus>, ScheduleError> {
+        for _ in 0..frames {
+            if let Some(status) = self.run_frame()? {
+                return Ok(Some(status));
             }
-            log::info!("Finished loading models");
         }
-        self.event_loop.run(move |event, _, control_flow| {
-            //*control_flow = ControlFlow::Wait;
+        Ok(None)
+    }
+
+    // Shuts the Application down without waiting for the end of a frame, for callers that stop stepping early.
+    // Dropping an Application that is still running calls it too
+    pub fn exit(&mut self) -> ExitStatus {
+        match self.state {
+            LoopState::Exited(status) => status,
+            // Only systems that were started up get shut down
+            LoopState::NotStarted | LoopState::Running => self.finish(ExitStatus::Success),
+        }
+    }
+
+    pub fn is_running(&self) -> bool {
+        self.state == LoopState::Running
+    }
+    pub fn exit_status(&self) -> Option<ExitStatus> {
+        match self.state {
+            LoopState::Exited(status) => Some(status),
+            _ => None,
+        }
+    }
+
+    // One frame, of dt seconds when given and of the time since the last frame otherwise
+    fn advance(&mut self, dt: Option<f32>) -> Result<Option<ExitStatus>, ScheduleError> {
+        match self.state {
+            LoopState::Exited(status) => return Ok(Some(status)),
+            LoopState::NotStarted => {
+                self.startup()?;
+                self.state = LoopState::Running;
+                self.current_time = time::Instant::now();
+                // A system can already have asked for a shutdown during startup
+                if shutdown_requested(&self.world) {
+                    return Ok(Some(self.finish(ExitStatus::Failure)));
+                }
+            }
+            LoopState::Running => {
+                // Systems added since the last frame are started before they first run
+                self.schedule.build_added_systems(&mut self.world)?;
+            }
+        }
+        // A fixed dt means the caller is stepping through frames as fast as it can, pacing would only slow it down
+        if dt.is_none() {
+            if let Some(mut limiter) = frame_limiter(&self.world) {
+                limiter.wait_for_next_frame();
+            }
+        }
+        let exit = match self.event_loop.take() {
+            Some(mut event_loop) => {
+                let exit = self.pump_frame(&mut event_loop, dt);
+                self.event_loop = Some(event_loop);
+                exit
+            }
+            None => {
+                let frame_time = dt.unwrap_or_else(|| self.frame_time());
+                run_update_stages(&mut self.schedule, &mut self.world, frame_time);
+                end_frame(
+                    &mut self.schedule,
+                    &mut self.world,
+                    &mut self.app_exit_reader,
+                )
+            }
+        };
+        Ok(exit.map(|status| self.finish(status)))
+    }
+
+    fn startup(&mut self) -> Result<(), ScheduleError> {
+        self.schedule.build()?;
+        // The main loop runs without them, but not the way anyone would expect
+        if !self.world.contains_resource::<Time>() {
+            log::warn!("No Time resource, frame times won't be scaled or paused, is the CorePlugin missing?");
+        }
+        if !self.world.contains_resource::<FixedTimestep>() {
+            log::warn!(
+                "No FixedTimestep resource, FixedUpdate won't run, is the CorePlugin missing?"
+            );
+        }
+        self.schedule.run_startup(&mut self.world);
+        log::info!("Finished startup");
+        Ok(())
+    }
+
+    fn finish(&mut self, status: ExitStatus) -> ExitStatus {
+        if self.state == LoopState::Running {
+            shutdown(&mut self.schedule, &mut self.world);
+        }
+        log::info!("Application exited with {:?}", status);
+        self.state = LoopState::Exited(status);
+        status
+    }
+
+    fn frame_time(&mut self) -> f32 {
+        let frame_time = self.current_time.elapsed().as_secs_f32();
+        self.current_time = time::Instant::now();
+        frame_time
+    }
+
+    // winit 0.28 has no pump_events, instead run_return is given control until the frame has been drawn and then
+    // told to exit, which hands control straight back. Every call handles what came in since the last one, runs
+    // the update stages on MainEventsCleared and draws on RedrawRequested. In LoopMode::Reactive the event loop
+    // is left waiting (ControlFlow::Wait) until there is input or a redraw request before the frame runs. Input is
+    // any window event plus mouse motion while focused, polled InputSources can't wake it
+    fn pump_frame(
+        &mut self,
+        event_loop: &mut EventLoop<()>,
+        dt: Option<f32>,
+    ) -> Option<ExitStatus> {
+        let mut exit = None;
+        let reactive = frame_limiter(&self.world)
+            .is_some_and(|limiter| limiter.active_mode() == LoopMode::Reactive);
+        let mut had_input = false;
+        let mut frame_started = false;
+        event_loop.run_return(|event, _, control_flow| {
             *control_flow = ControlFlow::Poll;
+            match event {
+                Event::WindowEvent { .. } => had_input = true,
+                // Mouse motion with the cursor grabbed only comes in as device events, those come in while the
+                // window is in the background too so they only count while it's focused
+                Event::DeviceEvent {
+                    event: DeviceEvent::MouseMotion { .. },
+                    ..
+                } => {
+                    had_input |=
+                        frame_limiter(&self.world).map_or(true, |limiter| limiter.is_focused());
+                }
+                _ => {}
+            }
             match event {
                 Event::WindowEvent { event, .. } => match event {
                     WindowEvent::Resized(window_size) => {
-                        // Need to send a message into the Renderer Class that a resize has occured
-                        self.transmitter
-                            .send((window_size.width, window_size.height))
-                            .expect("Failed to send data");
+                        self.world.send_event(WindowResized {
+                            width: window_size.width,
+                            height: window_size.height,
+                        });
+                        // Minimizing resizes the window to nothing on most platforms
+                        if let Some(mut limiter) = frame_limiter(&self.world) {
+                            limiter
+                                .set_minimized(window_size.width == 0 || window_size.height == 0);
+                        }
+                        if let Some(mut input) = input_consumer(&self.world) {
+                            input.set_window_size(window_size);
+                        }
+                    }
+                    // Finish the frame, then exit
+                    WindowEvent::CloseRequested { .. } => {
+                        self.world.send_event(WindowCloseRequested);
+                        exit = Some(ExitStatus::Success);
                     }
-                    // Leave the close requested event here for now
-                    WindowEvent::CloseRequested { .. } => *control_flow = ControlFlow::Exit,
+                    WindowEvent::Focused(focused) => {
+                        self.world.send_event(WindowFocused { focused });
+                        if let Some(mut limiter) = frame_limiter(&self.world) {
+                            limiter.set_focused(focused);
+                        }
+                        if let Some(mut input) = input_consumer(&self.world) {
+                            input.capture_focus(focused);
+                        }
+                    }
+                    // Quitting is the "quit" action of the InputMap now, see QuitOnAction
                     WindowEvent::KeyboardInput { ref input, .. } => {
-                        // Special casing is bad design, but I'll leave this for now
-
-                        if let Some(virtual_keycode) = input.virtual_keycode {
-                            if virtual_keycode == VirtualKeyCode::Escape {
-                                *control_flow = ControlFlow::Exit;
-                            } else if let Some(mut keyboard_input) =
-                                self.world.get_resource_mut::<InputConsumer>()
-                            {
+                        self.world.send_event(KeyboardEvent::from(input));
+                        if input.virtual_keycode.is_some() {
+                            if let Some(mut keyboard_input) = input_consumer(&self.world) {
                                 keyboard_input.capture_keyboard_input(input);
                             }
                         }
+                        if let Some(mut text_input) = text_input(&self.world) {
+                            text_input.capture_keyboard_input(input);
+                        }
+                    }
+                    WindowEvent::ReceivedCharacter(character) => {
+                        if let Some(mut text_input) = text_input(&self.world) {
+                            text_input.capture_character(character);
+                        }
+                    }
+                    WindowEvent::ModifiersChanged(modifiers) => {
+                        if let Some(mut text_input) = text_input(&self.world) {
+                            text_input.capture_modifiers(modifiers);
+                        }
+                    }
+                    WindowEvent::MouseInput { state, button, .. } => {
+                        if let Some(mut input) = input_consumer(&self.world) {
+                            input.capture_mouse_button(state, button);
+                        }
+                    }
+                    WindowEvent::CursorMoved { position, .. } => {
+                        if let Some(mut input) = input_consumer(&self.world) {
+                            input.capture_cursor_moved(position);
+                        }
+                    }
+                    WindowEvent::CursorLeft { .. } => {
+                        if let Some(mut input) = input_consumer(&self.world) {
+                            input.capture_cursor_left();
+                        }
+                    }
+                    WindowEvent::MouseWheel { delta, .. } => {
+                        if let Some(mut input) = input_consumer(&self.world) {
+                            input.capture_mouse_wheel(delta);
+                        }
                     }
                     _ => {
                         // Need to pass the pressed variable into a keyboard class
@@ -104,46 +365,191 @@ impl Application {
                         //println!("Keyboard Input Virtual_keycode: {:?}", event);
                     }
                 },
+                Event::DeviceEvent {
+                    event: DeviceEvent::MouseMotion { delta },
+                    ..
+                } => {
+                    if let Some(mut input) = input_consumer(&self.world) {
+                        input.capture_mouse_motion(delta);
+                    }
+                }
                 Event::MainEventsCleared => {
-                    // Handle Time Step after user input
-                    let new_time = time::Instant::now();
-                    let frame_time = current_time.elapsed().as_secs_f32(); //new_time.duration_since(current_time).as_secs_f32();
-                                                                           // TODO: Properly store deltaTime in world
-                                                                           //println!("FrameTime {}", frame_time);
-                    self.world.update_dt(frame_time);
-
-                    current_time = new_time;
-                    // TODO: This works, for now, with a small number of systems, iterating through them 3 times per update isn't
-                    // terrible (right now) just not ideal, perhaps it'd be worth storing systems in different buckets?
-                    // What can I do with a HashMap<Key, Vec<Systems>>??? something to think about
-                    for i in &mut self.systems.values_mut() {
-                        if i.get_run_stage() == RunStage::Update {
-                            i.as_mut().run(&mut self.world);
-                        }
+                    let redraw_requested = frame_limiter(&self.world)
+                        .is_some_and(|mut limiter| limiter.take_redraw_request());
+                    if reactive && !had_input && !redraw_requested {
+                        return;
                     }
+                    // Handle Time Step after user input
+                    let frame_time = dt.unwrap_or_else(|| self.frame_time());
+                    run_update_stages(&mut self.schedule, &mut self.world, frame_time);
+                    frame_started = true;
                 }
                 Event::RedrawRequested(_window_id) => {
-                    // Redraw frame
-                    for i in &mut self.systems.values_mut() {
-                        if i.get_run_stage() == RunStage::RedrawUpdate {
-                            i.as_mut().run(&mut self.world);
-                        }
+                    // The platform asked for a redraw (the window was uncovered) while a Reactive loop was idle
+                    if !frame_started {
+                        let frame_time = dt.unwrap_or_else(|| self.frame_time());
+                        run_update_stages(&mut self.schedule, &mut self.world, frame_time);
+                        frame_started = true;
                     }
+                    // Redraw frame
+                    self.schedule
+                        .run_stage(RunStage::RedrawUpdate, &mut self.world);
                 }
                 Event::RedrawEventsCleared => {
-                    for i in &mut self.systems.values_mut() {
-                        if i.get_run_stage() == RunStage::LateUpdate {
-                            i.as_mut().run(&mut self.world);
-                        }
+                    // Nothing happened, keep waiting for input
+                    if !frame_started {
+                        *control_flow = ControlFlow::Wait;
+                        return;
                     }
-                }
-                Event::LoopDestroyed => {
-                    for i in &mut self.systems.values_mut() {
-                        i.as_mut().shutdown(&mut self.world);
+                    if let Some(status) = end_frame(
+                        &mut self.schedule,
+                        &mut self.world,
+                        &mut self.app_exit_reader,
+                    ) {
+                        exit = exit.or(Some(status));
                     }
+                    // The frame is done, hand control back to the caller
+                    *control_flow = ControlFlow::Exit;
                 }
                 _ => (),
             }
         });
+        exit
     }
 }
+
+// A stepped Application dropped without exit still shuts its systems down
+impl Drop for Application {
+    fn drop(&mut self) {
+        if self.state == LoopState::Running {
+            self.exit();
+        }
+    }
+}
+
+// Everything in a frame before drawing: state transitions, EarlyUpdate, as many FixedUpdate steps as frame_time
+// covers, then Update
+fn run_update_stages(schedule: &mut Schedule, world: &mut World, frame_time: f32) {
+    // Whatever input came in from the window is overwritten by the recorded frame
+    let frame_time = play_back_input(world).unwrap_or(frame_time);
+    // Without the CorePlugin there is no Time resource to scale or pause the frame time
+    let delta = if world.contains_resource::<Time>() {
+        let mut time = world.get_resource_mut::<Time>().unwrap();
+        time.update(frame_time);
+        time.delta()
+    } else {
+        frame_time
+    };
+    // Transitions queued last frame, every system sees the same state for the whole frame
+    schedule.apply_state_transitions(world);
+    schedule.run_stage(RunStage::EarlyUpdate, world);
+    // Scaled time, so slowing down or pausing the Time resource slows down or pauses the simulation too. No
+    // FixedTimestep resource, no FixedUpdate, startup warns about it
+    let steps = if world.contains_resource::<FixedTimestep>() {
+        world
+            .get_resource_mut::<FixedTimestep>()
+            .unwrap()
+            .accumulate(delta)
+    } else {
+        0
+    };
+    for _ in 0..steps {
+        store_previous_transforms(world);
+        schedule.run_stage(RunStage::FixedUpdate, world);
+    }
+    schedule.run_stage(RunStage::Update, world);
+}
+
+// LateUpdate then the end of frame bookkeeping, hands back how the Application should exit if anything this frame
+// asked it to
+fn end_frame(
+    schedule: &mut Schedule,
+    world: &mut World,
+    app_exit_reader: &mut EventReader<AppExit>,
+) -> Option<ExitStatus> {
+    schedule.run_stage(RunStage::LateUpdate, world);
+    // LateUpdate is the last chance to exit this frame, checked before its events are cleared
+    let exit = frame_exit(world, app_exit_reader);
+    // End of the frame, removals and events older than a frame have been seen by every system
+    world.clear_trackers();
+    world.update_events();
+    if let Some(mut input) = input_consumer(world) {
+        input.end_frame();
+    }
+    if let Some(mut text_input) = text_input(world) {
+        text_input.end_frame();
+    }
+    if world.contains_resource::<Profiler>() {
+        world.get_resource_mut::<Profiler>().unwrap().end_frame();
+    }
+    exit
+}
+
+fn shutdown(schedule: &mut Schedule, world: &mut World) {
+    schedule.run_shutdown(world);
+    if world.contains_resource::<Profiler>() {
+        let profiler = world.get_resource::<Profiler>().unwrap();
+        log::info!("System timings:\n{}", profiler.report());
+        if let Err(error) = profiler.write_chrome_trace() {
+            log::warn!("Failed to write the Chrome trace: {}", error);
+        }
+    }
+}
+
+// Failure wins over AppExit when both happened in the same frame
+fn frame_exit(world: &World, app_exit_reader: &mut EventReader<AppExit>) -> Option<ExitStatus> {
+    // AppExit is registered by the CorePlugin
+    let exit_requested = match world.get_resource::<Events<AppExit>>() {
+        Some(app_exit) => app_exit_reader.read(&app_exit).next().is_some(),
+        None => false,
+    };
+    if shutdown_requested(world) {
+        Some(ExitStatus::Failure)
+    } else if exit_requested {
+        Some(ExitStatus::Success)
+    } else {
+        None
+    }
+}
+
+// Only there when the InputPlugin was added
+fn input_consumer(world: &World) -> Option<RefMut<'_, InputConsumer>> {
+    if !world.contains_resource::<InputConsumer>() {
+        return None;
+    }
+    world.get_resource_mut::<InputConsumer>()
+}
+
+fn text_input(world: &World) -> Option<RefMut<'_, TextInput>> {
+    if !world.contains_resource::<TextInput>() {
+        return None;
+    }
+    world.get_resource_mut::<TextInput>()
+}
+
+// The recorded frame time while an InputPlayback is playing
+fn play_back_input(world: &World) -> Option<f32> {
+    if !world.contains_resource::<InputPlayback>() {
+        return None;
+    }
+    world
+        .get_resource_mut::<InputPlayback>()
+        .unwrap()
+        .play_frame(world)
+}
+
+fn frame_limiter(world: &World) -> Option<RefMut<'_, FrameLimiter>> {
+    if !world.contains_resource::<FrameLimiter>() {
+        return None;
+    }
+    world.get_resource_mut::<FrameLimiter>()
+}
+
+// Set when a system failed with ErrorPolicy::Shutdown
+fn shutdown_requested(world: &World) -> bool {
+    world.contains_resource::<SystemErrors>()
+        && world
+            .get_resource::<SystemErrors>()
+            .unwrap()
+            .shutdown_requested()
+}
@@ -0,0 +1,64 @@
+// Resource driving RunStage::FixedUpdate. Every frame the frame time is added to the accumulator and the fixed
+// stage runs once for every whole step in it, so systems in that stage always advance by exactly step seconds
+// regardless of frame rate. The leftover time is exposed as alpha for rendering to blend between the transform
+// before and after the last step.
+#[derive(Debug, Copy, Clone)]
+pub struct FixedTimestep {
+    step: f32,
+    // A frame that took too long would otherwise need more steps than can be run in a frame, which makes the
+    // next frame even longer. Time beyond max_steps is dropped and the simulation slows down instead
+    max_steps: u32,
+    accumulator: f32,
+}
+
+impl FixedTimestep {
+    pub const DEFAULT_RATE: f32 = 60.0;
+    pub const DEFAULT_MAX_STEPS: u32 = 5;
+
+    // rate is in steps per second
+    pub fn new(rate: f32) -> Self {
+        Self {
+            step: 1.0 / rate,
+            max_steps: Self::DEFAULT_MAX_STEPS,
+            accumulator: 0.0,
+        }
+    }
+    pub fn with_max_steps(mut self, max_steps: u32) -> Self {
+        self.max_steps = max_steps;
+        self
+    }
+    pub fn set_rate(&mut self, rate: f32) {
+        self.step = 1.0 / rate;
+    }
+    pub fn rate(&self) -> f32 {
+        1.0 / self.step
+    }
+    // Seconds simulated by every run of the fixed stage, this is the dt FixedUpdate systems should use
+    pub fn step(&self) -> f32 {
+        self.step
+    }
+    pub fn max_steps(&self) -> u32 {
+        self.max_steps
+    }
+    // Adds the frame time and returns how many fixed steps to run this frame
+    pub fn accumulate(&mut self, dt: f32) -> u32 {
+        self.accumulator += dt;
+        let steps = (self.accumulator / self.step) as u32;
+        if steps > self.max_steps {
+            self.accumulator %= self.step;
+            return self.max_steps;
+        }
+        self.accumulator -= steps as f32 * self.step;
+        steps
+    }
+    // How far between the last fixed step and the next one the current frame is, from 0 to 1
+    pub fn alpha(&self) -> f32 {
+        (self.accumulator / self.step).clamp(0.0, 1.0)
+    }
+}
+
+impl Default for FixedTimestep {
+    fn default() -> Self {
+        Self::new(Self::DEFAULT_RATE)
+    }
+}
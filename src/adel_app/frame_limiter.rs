@@ -0,0 +1,152 @@
+use std::time::{Duration, Instant};
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum LoopMode {
+    // Next frame as soon as the last one is done, keeps a core busy
+    Uncapped,
+    // Frames per second, the loop sleeps between frames and spins for the last moment to start them on time
+    TargetFps(f32),
+    // Only runs a frame when there was window input or a redraw was requested, otherwise blocks in the event loop.
+    // Meant for tools and editors, anything animating has to keep calling FrameLimiter::request_redraw. Gamepads
+    // and other InputSources are only polled while a frame runs so they can't wake the loop, request redraws for
+    // as long as they need to be read
+    Reactive,
+}
+
+// Resource picking how the Application paces its frames. While the window is unfocused or minimized the slower
+// of the mode and the unfocused mode is used, by default that throttles to UNFOCUSED_FPS so a window in the
+// background doesn't spin a core while a Reactive loop keeps waiting for input
+#[derive(Debug, Clone)]
+pub struct FrameLimiter {
+    mode: LoopMode,
+    unfocused_mode: Option<LoopMode>,
+    // How long before a frame is due to stop sleeping and start spinning, sleep is only accurate to about a
+    // millisecond on most platforms
+    spin_threshold: Duration,
+    focused: bool,
+    minimized: bool,
+    redraw_requested: bool,
+    last_frame: Option<Instant>,
+}
+
+impl FrameLimiter {
+    pub const UNFOCUSED_FPS: f32 = 10.0;
+    pub const DEFAULT_SPIN_THRESHOLD: Duration = Duration::from_micros(1500);
+
+    pub fn new(mode: LoopMode) -> Self {
+        Self {
+            mode,
+            unfocused_mode: Some(LoopMode::TargetFps(Self::UNFOCUSED_FPS)),
+            spin_threshold: Self::DEFAULT_SPIN_THRESHOLD,
+            focused: true,
+            minimized: false,
+            redraw_requested: false,
+            last_frame: None,
+        }
+    }
+    // None keeps using the regular mode while unfocused
+    pub fn with_unfocused_mode(mut self, unfocused_mode: Option<LoopMode>) -> Self {
+        self.unfocused_mode = unfocused_mode;
+        self
+    }
+    pub fn with_spin_threshold(mut self, spin_threshold: Duration) -> Self {
+        self.spin_threshold = spin_threshold;
+        self
+    }
+    pub fn mode(&self) -> LoopMode {
+        self.mode
+    }
+    pub fn set_mode(&mut self, mode: LoopMode) {
+        self.mode = mode;
+    }
+    pub fn unfocused_mode(&self) -> Option<LoopMode> {
+        self.unfocused_mode
+    }
+    pub fn set_unfocused_mode(&mut self, unfocused_mode: Option<LoopMode>) {
+        self.unfocused_mode = unfocused_mode;
+    }
+    // The mode the next frame runs with, taking focus into account
+    pub fn active_mode(&self) -> LoopMode {
+        match self.unfocused_mode {
+            Some(unfocused_mode) if !self.focused || self.minimized => {
+                slower_mode(self.mode, unfocused_mode)
+            }
+            _ => self.mode,
+        }
+    }
+    pub fn is_focused(&self) -> bool {
+        self.focused
+    }
+    pub fn is_minimized(&self) -> bool {
+        self.minimized
+    }
+    // Makes a Reactive loop run the next frame without waiting for input
+    pub fn request_redraw(&mut self) {
+        self.redraw_requested = true;
+    }
+    pub(crate) fn take_redraw_request(&mut self) -> bool {
+        std::mem::take(&mut self.redraw_requested)
+    }
+    pub(crate) fn set_focused(&mut self, focused: bool) {
+        self.focused = focused;
+    }
+    pub(crate) fn set_minimized(&mut self, minimized: bool) {
+        self.minimized = minimized;
+    }
+    // Blocks until the next frame is due when running at a target fps, returns right away otherwise
+    pub(crate) fn wait_for_next_frame(&mut self) {
+        let fps = match self.active_mode() {
+            LoopMode::TargetFps(fps) if fps > 0.0 => fps,
+            _ => {
+                self.last_frame = Some(Instant::now());
+                return;
+            }
+        };
+        let frame_duration = Duration::from_secs_f32(1.0 / fps);
+        let deadline = match self.last_frame {
+            Some(last_frame) => last_frame + frame_duration,
+            None => Instant::now(),
+        };
+        let now = Instant::now();
+        if now < deadline {
+            let remaining = deadline - now;
+            if remaining > self.spin_threshold {
+                std::thread::sleep(remaining - self.spin_threshold);
+            }
+            while Instant::now() < deadline {
+                std::hint::spin_loop();
+            }
+            self.last_frame = Some(deadline);
+        } else {
+            // Running behind, start counting from now instead of trying to catch up
+            self.last_frame = Some(now);
+        }
+    }
+}
+
+// Reactive only runs frames on input so it's the slowest
+fn slower_mode(mode: LoopMode, other: LoopMode) -> LoopMode {
+    match (pacing(mode), pacing(other)) {
+        (LoopMode::Reactive, _) | (_, LoopMode::Reactive) => LoopMode::Reactive,
+        (LoopMode::TargetFps(fps), LoopMode::TargetFps(other_fps)) => {
+            LoopMode::TargetFps(fps.min(other_fps))
+        }
+        (LoopMode::TargetFps(fps), LoopMode::Uncapped)
+        | (LoopMode::Uncapped, LoopMode::TargetFps(fps)) => LoopMode::TargetFps(fps),
+        (LoopMode::Uncapped, LoopMode::Uncapped) => LoopMode::Uncapped,
+    }
+}
+
+// A TargetFps of 0 or less doesn't wait at all, see wait_for_next_frame
+fn pacing(mode: LoopMode) -> LoopMode {
+    match mode {
+        LoopMode::TargetFps(fps) if fps <= 0.0 => LoopMode::Uncapped,
+        mode => mode,
+    }
+}
+
+impl Default for FrameLimiter {
+    fn default() -> Self {
+        Self::new(LoopMode::Uncapped)
+    }
+}
@@ -1,3 +1,9 @@
 mod app;
+mod fixed_timestep;
+mod frame_limiter;
+mod plugin;
 
 pub use app::*;
+pub use fixed_timestep::*;
+pub use frame_limiter::*;
+pub use plugin::*;
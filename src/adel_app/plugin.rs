@@ -0,0 +1,208 @@
+use crate::adel_app::{AppExit, Application, FixedTimestep, FrameLimiter};
+use crate::adel_ecs::{IntoSystemDescriptor, Schedule, State, StateData, SystemErrors, World};
+use crate::adel_input::{InputPlugin, KeyboardCameraPlugin};
+use crate::adel_renderer::RendererPlugin;
+use crate::adel_time::TimePlugin;
+use crate::adel_winit::{
+    KeyboardEvent, WindowCloseRequested, WindowFocused, WindowPlugin, WindowResized,
+};
+use std::any::TypeId;
+use winit::event_loop::EventLoop;
+
+// A Plugin bundles everything one engine feature needs (systems, resources and events) so an Application is
+// put together by picking features instead of editing Application::new
+pub trait Plugin {
+    fn build(&self, app: &mut AppBuilder);
+    fn name(&self) -> &str {
+        std::any::type_name::<Self>()
+    }
+}
+
+pub struct AppBuilder {
+    world: World,
+    schedule: Schedule,
+    event_loop: Option<EventLoop<()>>,
+    // Every plugin added so far, a plugin is only ever built once
+    plugins: Vec<TypeId>,
+}
+
+impl AppBuilder {
+    pub fn new(world: World) -> Self {
+        Self {
+            world,
+            schedule: Schedule::new(),
+            event_loop: None,
+            plugins: Vec::new(),
+        }
+    }
+    pub fn add_plugin<P: Plugin + 'static>(&mut self, plugin: P) -> &mut Self {
+        self.add_boxed_plugin(TypeId::of::<P>(), Box::new(plugin));
+        self
+    }
+    // Plugins are built in the order they were added to the group
+    pub fn add_plugins(&mut self, group: PluginGroup) -> &mut Self {
+        for (type_id, plugin) in group.plugins {
+            if let Some(plugin) = plugin {
+                self.add_boxed_plugin(type_id, plugin);
+            }
+        }
+        self
+    }
+    fn add_boxed_plugin(&mut self, type_id: TypeId, plugin: Box<dyn Plugin>) {
+        if self.plugins.contains(&type_id) {
+            log::warn!("Plugin {} was already added, skipping it", plugin.name());
+            return;
+        }
+        self.plugins.push(type_id);
+        log::info!("Building plugin {}", plugin.name());
+        plugin.build(self);
+    }
+    pub fn has_plugin<P: Plugin + 'static>(&self) -> bool {
+        self.plugins.contains(&TypeId::of::<P>())
+    }
+    pub fn add_system(&mut self, system: impl IntoSystemDescriptor) -> &mut Self {
+        self.schedule.add_system(system);
+        self
+    }
+    pub fn add_system_on_enter<S: StateData>(
+        &mut self,
+        state: S,
+        system: impl IntoSystemDescriptor,
+    ) -> &mut Self {
+        self.schedule.add_system_on_enter(state, system);
+        self
+    }
+    pub fn add_system_on_exit<S: StateData>(
+        &mut self,
+        state: S,
+        system: impl IntoSystemDescriptor,
+    ) -> &mut Self {
+        self.schedule.add_system_on_exit(state, system);
+        self
+    }
+    // Inserts the State<S> resource, the on_enter systems of initial run right after startup
+    pub fn add_state<S: StateData>(&mut self, initial: S) -> &mut Self {
+        self.world.insert_resource(State::new(initial));
+        self.schedule.add_state::<S>();
+        self
+    }
+    pub fn insert_resource<R: 'static>(&mut self, resource: R) -> &mut Self {
+        self.world.insert_resource(resource);
+        self
+    }
+    pub fn add_event<T: 'static>(&mut self) -> &mut Self {
+        self.world.add_event::<T>();
+        self
+    }
+    // For plugins that need to look at what earlier plugins set up
+    pub fn world(&mut self) -> &mut World {
+        &mut self.world
+    }
+    // Set by the WindowPlugin, an Application without an event loop can only be run headless
+    pub fn set_event_loop(&mut self, event_loop: EventLoop<()>) -> &mut Self {
+        self.event_loop = Some(event_loop);
+        self
+    }
+    pub fn build(self) -> Application {
+        Application::from_parts(self.world, self.schedule, self.event_loop)
+    }
+}
+
+// An ordered set of plugins where single plugins can be switched off or swapped for a replacement before the
+// group is handed to AppBuilder::add_plugins
+pub struct PluginGroup {
+    // None marks a disabled plugin
+    plugins: Vec<(TypeId, Option<Box<dyn Plugin>>)>,
+}
+
+impl PluginGroup {
+    pub fn new() -> Self {
+        Self {
+            plugins: Vec::new(),
+        }
+    }
+    pub fn add<P: Plugin + 'static>(mut self, plugin: P) -> Self {
+        self.plugins
+            .push((TypeId::of::<P>(), Some(Box::new(plugin))));
+        self
+    }
+    pub fn disable<P: Plugin + 'static>(mut self) -> Self {
+        match self.position::<P>() {
+            Some(index) => self.plugins[index].1 = None,
+            None => log::warn!(
+                "Can't disable {}, it isn't part of the group",
+                std::any::type_name::<P>()
+            ),
+        }
+        self
+    }
+    // Swaps in a differently configured instance of a plugin, keeping its place in the group
+    pub fn set<P: Plugin + 'static>(mut self, plugin: P) -> Self {
+        match self.position::<P>() {
+            Some(index) => self.plugins[index].1 = Some(Box::new(plugin)),
+            None => self = self.add(plugin),
+        }
+        self
+    }
+    // Replaces a plugin with a plugin of a different type, R takes the place of P in the build order
+    pub fn replace<P: Plugin + 'static, R: Plugin + 'static>(mut self, plugin: R) -> Self {
+        match self.position::<P>() {
+            Some(index) => self.plugins[index] = (TypeId::of::<R>(), Some(Box::new(plugin))),
+            None => self = self.add(plugin),
+        }
+        self
+    }
+    fn position<P: Plugin + 'static>(&self) -> Option<usize> {
+        self.plugins
+            .iter()
+            .position(|(type_id, _)| *type_id == TypeId::of::<P>())
+    }
+}
+
+impl Default for PluginGroup {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+// Everything Application::new used to set up by hand: a window, keyboard input, the renderer and the keyboard
+// controlled camera
+pub struct DefaultPlugins;
+
+impl DefaultPlugins {
+    pub fn build() -> PluginGroup {
+        PluginGroup::new()
+            .add(CorePlugin)
+            .add(WindowPlugin)
+            .add(InputPlugin)
+            .add(RendererPlugin)
+            .add(KeyboardCameraPlugin)
+    }
+}
+
+// Resources and events the main loop itself relies on, needed by every Application windowed or headless
+pub struct CorePlugin;
+
+impl Plugin for CorePlugin {
+    fn build(&self, app: &mut AppBuilder) {
+        // Window events coming out of winit, readable by any system
+        app.add_event::<WindowResized>()
+            .add_event::<WindowCloseRequested>()
+            .add_event::<WindowFocused>()
+            .add_event::<KeyboardEvent>()
+            .add_event::<AppExit>();
+        app.add_plugin(TimePlugin);
+        // Worlds can come with their own simulation rate
+        if !app.world().contains_resource::<FixedTimestep>() {
+            app.insert_resource(FixedTimestep::default());
+        }
+        // Same for the frame pacing
+        if !app.world().contains_resource::<FrameLimiter>() {
+            app.insert_resource(FrameLimiter::default());
+        }
+        // And for the default ErrorPolicy
+        if !app.world().contains_resource::<SystemErrors>() {
+            app.insert_resource(SystemErrors::default());
+        }
+    }
+}
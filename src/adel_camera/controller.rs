@@ -0,0 +1,411 @@
+use crate::adel_camera::Camera;
+use crate::adel_ecs::{RunStage, System, SystemResult, World};
+use crate::adel_input::{not_typing, InputConsumer, InputMap};
+use crate::adel_renderer::definitions::TransformComponent;
+use crate::adel_time::Time;
+use anyhow::Context;
+use nalgebra::{Vector2, Vector3};
+use winit::window::{CursorGrabMode, Window};
+
+// Looking straight up or down flips the view over, stop just short of it
+const MAX_PITCH: f32 = 1.5;
+
+// First-person fly camera, add it next to the KeyboardComponent on the camera entity and KeyboardHandler leaves
+// the entity to the FlyCameraController. Moves where it looks with the move_* axes, looks around with the mouse
+// and the look_* axes. The mouse only turns the camera while the cursor is grabbed or the "mouse_look" action is
+// held, "sprint" multiplies the speed
+#[derive(Debug, Clone)]
+pub struct FlyCamera {
+    // Units per second
+    pub move_speed: f32,
+    // Radians per second at full tilt of the look axes
+    pub look_speed: f32,
+    // Radians per pixel of mouse motion
+    pub sensitivity: f32,
+    pub invert_y: bool,
+    pub sprint_multiplier: f32,
+    // Hides and locks the cursor to the window while it's focused and nothing is being typed
+    pub grab_cursor: bool,
+}
+
+impl FlyCamera {
+    pub const DEFAULT_MOVE_SPEED: f32 = 3.0;
+    pub const DEFAULT_LOOK_SPEED: f32 = 1.5;
+    pub const DEFAULT_SENSITIVITY: f32 = 0.002;
+    pub const DEFAULT_SPRINT_MULTIPLIER: f32 = 3.0;
+
+    pub fn new() -> Self {
+        Self {
+            move_speed: Self::DEFAULT_MOVE_SPEED,
+            look_speed: Self::DEFAULT_LOOK_SPEED,
+            sensitivity: Self::DEFAULT_SENSITIVITY,
+            invert_y: false,
+            sprint_multiplier: Self::DEFAULT_SPRINT_MULTIPLIER,
+            grab_cursor: true,
+        }
+    }
+    pub fn with_move_speed(mut self, move_speed: f32) -> Self {
+        self.move_speed = move_speed;
+        self
+    }
+    pub fn with_look_speed(mut self, look_speed: f32) -> Self {
+        self.look_speed = look_speed;
+        self
+    }
+    pub fn with_sensitivity(mut self, sensitivity: f32) -> Self {
+        self.sensitivity = sensitivity;
+        self
+    }
+    pub fn with_invert_y(mut self, invert_y: bool) -> Self {
+        self.invert_y = invert_y;
+        self
+    }
+    pub fn with_sprint_multiplier(mut self, sprint_multiplier: f32) -> Self {
+        self.sprint_multiplier = sprint_multiplier;
+        self
+    }
+    pub fn with_grab_cursor(mut self, grab_cursor: bool) -> Self {
+        self.grab_cursor = grab_cursor;
+        self
+    }
+}
+
+// Turntable camera circling focus, add it next to the KeyboardComponent on the camera entity. Dragging with
+// "orbit_rotate" held or the look_* axes rotate around the focus, dragging with "orbit_pan" held or the
+// move_right/move_up axes move the focus, scrolling or move_forward zooms. yaw, pitch and distance are taken from
+// where the entity is the first time the controller sees it, so the view doesn't jump
+#[derive(Debug, Clone)]
+pub struct OrbitCamera {
+    pub focus: Vector3<f32>,
+    pub distance: f32,
+    pub yaw: f32,
+    pub pitch: f32,
+    pub min_distance: f32,
+    pub max_distance: f32,
+    // Radians per pixel dragged
+    pub rotate_sensitivity: f32,
+    // Radians per second at full tilt of the look axes
+    pub look_speed: f32,
+    // Fraction of the distance moved per pixel dragged, so panning feels the same zoomed in or out
+    pub pan_sensitivity: f32,
+    // Fraction of the distance moved per second at full tilt of the move axes
+    pub pan_speed: f32,
+    // Scrolling one line scales the distance by e^-zoom_sensitivity
+    pub zoom_sensitivity: f32,
+    pub invert_y: bool,
+    initialized: bool,
+}
+
+impl OrbitCamera {
+    pub const DEFAULT_ROTATE_SENSITIVITY: f32 = 0.005;
+    pub const DEFAULT_LOOK_SPEED: f32 = 1.5;
+    pub const DEFAULT_PAN_SENSITIVITY: f32 = 0.002;
+    pub const DEFAULT_PAN_SPEED: f32 = 1.0;
+    pub const DEFAULT_ZOOM_SENSITIVITY: f32 = 0.1;
+
+    pub fn new(focus: Vector3<f32>) -> Self {
+        Self {
+            focus,
+            distance: 1.0,
+            yaw: 0.0,
+            pitch: 0.0,
+            min_distance: 0.1,
+            max_distance: 100.0,
+            rotate_sensitivity: Self::DEFAULT_ROTATE_SENSITIVITY,
+            look_speed: Self::DEFAULT_LOOK_SPEED,
+            pan_sensitivity: Self::DEFAULT_PAN_SENSITIVITY,
+            pan_speed: Self::DEFAULT_PAN_SPEED,
+            zoom_sensitivity: Self::DEFAULT_ZOOM_SENSITIVITY,
+            invert_y: false,
+            initialized: false,
+        }
+    }
+    pub fn with_distance_limits(mut self, min_distance: f32, max_distance: f32) -> Self {
+        self.min_distance = min_distance;
+        self.max_distance = max_distance;
+        self
+    }
+    pub fn with_rotate_sensitivity(mut self, rotate_sensitivity: f32) -> Self {
+        self.rotate_sensitivity = rotate_sensitivity;
+        self
+    }
+    pub fn with_pan_sensitivity(mut self, pan_sensitivity: f32) -> Self {
+        self.pan_sensitivity = pan_sensitivity;
+        self
+    }
+    pub fn with_zoom_sensitivity(mut self, zoom_sensitivity: f32) -> Self {
+        self.zoom_sensitivity = zoom_sensitivity;
+        self
+    }
+    pub fn with_invert_y(mut self, invert_y: bool) -> Self {
+        self.invert_y = invert_y;
+        self
+    }
+    // Where the camera sits, behind the focus along the view direction
+    pub fn position(&self) -> Vector3<f32> {
+        self.focus - forward(self.pitch, self.yaw) * self.distance
+    }
+    fn look_from(&mut self, transform: &TransformComponent) {
+        let offset = transform.translation - self.focus;
+        let distance = offset.norm();
+        if distance > f32::EPSILON {
+            let forward = -offset / distance;
+            self.distance = distance;
+            self.pitch = (-forward.y).asin();
+            self.yaw = forward.x.atan2(forward.z);
+        } else {
+            self.pitch = transform.rotation.x;
+            self.yaw = transform.rotation.y;
+        }
+        self.clamp();
+        self.initialized = true;
+    }
+    fn clamp(&mut self) {
+        self.pitch = self.pitch.clamp(-MAX_PITCH, MAX_PITCH);
+        self.yaw %= 2.0 * std::f32::consts::PI;
+        self.distance = self.distance.clamp(self.min_distance, self.max_distance);
+    }
+}
+
+// Direction the camera looks in for a rotation of (pitch, yaw, 0), same as Camera::set_view_yxz. y points down
+fn forward(pitch: f32, yaw: f32) -> Vector3<f32> {
+    Vector3::new(
+        pitch.cos() * yaw.sin(),
+        -pitch.sin(),
+        pitch.cos() * yaw.cos(),
+    )
+}
+
+fn right(yaw: f32) -> Vector3<f32> {
+    Vector3::new(yaw.cos(), 0.0, -yaw.sin())
+}
+
+// Up on the screen, which tilts with the pitch
+fn screen_up(pitch: f32, yaw: f32) -> Vector3<f32> {
+    -Vector3::new(
+        yaw.sin() * pitch.sin(),
+        pitch.cos(),
+        yaw.cos() * pitch.sin(),
+    )
+}
+
+// What the controllers read from the InputMap and InputConsumer each frame
+struct ControllerInput {
+    // (right, up, forward) and (pitch, yaw, roll) like move_in_plane_xz
+    movement: Vector3<f32>,
+    look: Vector3<f32>,
+    mouse_delta: Vector2<f32>,
+    scroll: f32,
+    mouse_look: bool,
+    sprint: bool,
+    orbit_rotate: bool,
+    orbit_pan: bool,
+    focused: bool,
+}
+
+impl ControllerInput {
+    fn read(world: &World) -> anyhow::Result<Self> {
+        let input_map = world
+            .get_resource::<InputMap>()
+            .context("Camera controllers need the InputMap resource, add the InputPlugin")?;
+        let input_consumer = world
+            .get_resource::<InputConsumer>()
+            .context("Camera controllers need the InputConsumer resource, add the InputPlugin")?;
+        let axis = |name| input_map.axis(&input_consumer, name);
+        let pressed = |name| input_map.pressed(&input_consumer, name);
+        Ok(Self {
+            movement: Vector3::new(axis("move_right"), axis("move_up"), axis("move_forward")),
+            look: Vector3::new(axis("look_pitch"), axis("look_yaw"), 0.0),
+            mouse_delta: input_consumer.mouse_delta(),
+            scroll: input_consumer.scroll_delta().y,
+            mouse_look: pressed("mouse_look"),
+            sprint: pressed("sprint"),
+            orbit_rotate: pressed("orbit_rotate"),
+            orbit_pan: pressed("orbit_pan"),
+            focused: input_consumer.is_focused(),
+        })
+    }
+}
+
+// Moves every entity with a FlyCamera and points the Camera through the last one. Runs every frame in Update on
+// the real frame time so mouse motion is applied once per frame and the camera keeps working while paused
+pub struct FlyCameraController {
+    name: &'static str,
+    cursor_grabbed: bool,
+}
+
+impl FlyCameraController {
+    pub fn new() -> Self {
+        Self {
+            name: "FlyCameraController",
+            cursor_grabbed: false,
+        }
+    }
+    fn grab_cursor(&mut self, world: &World, grab: bool) {
+        if grab == self.cursor_grabbed || !world.contains_resource::<Window>() {
+            return;
+        }
+        let window = world.get_resource::<Window>().unwrap();
+        let result = if grab {
+            // Locked keeps the cursor in place, not every platform has it
+            window
+                .set_cursor_grab(CursorGrabMode::Locked)
+                .or_else(|_| window.set_cursor_grab(CursorGrabMode::Confined))
+        } else {
+            window.set_cursor_grab(CursorGrabMode::None)
+        };
+        if let Err(error) = result {
+            log::warn!("Failed to change the cursor grab: {}", error);
+        }
+        window.set_cursor_visible(!grab);
+        self.cursor_grabbed = grab;
+    }
+}
+
+impl System for FlyCameraController {
+    fn startup(&mut self, _world: &mut World) -> SystemResult {
+        Ok(())
+    }
+    fn run(&mut self, world: &mut World) -> SystemResult {
+        // Not a run condition, the cursor has to be let go of when a text field takes the focus
+        let typing = !not_typing(world);
+        let input = ControllerInput::read(world)?;
+        let dt = world
+            .get_resource::<Time>()
+            .context("Camera controllers need the Time resource, add the CorePlugin")?
+            .raw_delta();
+
+        let mut camera_query = world
+            .query::<(&mut TransformComponent, &FlyCamera)>()
+            .context("FlyCameraController failed to query the fly cameras")?;
+        let mut camera = world
+            .get_resource_mut::<Camera>()
+            .context("Camera controllers need the Camera resource")?;
+        let mut grab = false;
+        for (transform, fly_camera) in camera_query.iter() {
+            grab |= fly_camera.grab_cursor && input.focused && !typing;
+            if typing {
+                continue;
+            }
+            // The look_* axes are rates, the mouse delta is already per frame and mustn't be bound to them too
+            let mut look = input.look * fly_camera.look_speed * dt;
+            if self.cursor_grabbed || input.mouse_look {
+                let y_sign = if fly_camera.invert_y { 1.0 } else { -1.0 };
+                look += Vector3::new(y_sign * input.mouse_delta.y, input.mouse_delta.x, 0.0)
+                    * fly_camera.sensitivity;
+            }
+            transform.rotation += look;
+            transform.rotation.x = transform.rotation.x.clamp(-MAX_PITCH, MAX_PITCH);
+            transform.rotation.y %= 2.0 * std::f32::consts::PI;
+
+            let (pitch, yaw) = (transform.rotation.x, transform.rotation.y);
+            let up = Vector3::new(0.0, -1.0, 0.0);
+            let direction = right(yaw) * input.movement.x
+                + up * input.movement.y
+                + forward(pitch, yaw) * input.movement.z;
+            let direction = if direction.norm_squared() > 1.0 {
+                direction.normalize()
+            } else {
+                direction
+            };
+            let speed = if input.sprint {
+                fly_camera.move_speed * fly_camera.sprint_multiplier
+            } else {
+                fly_camera.move_speed
+            };
+            transform.translation += direction * speed * dt;
+            camera.set_view_yxz(transform.translation, transform.rotation);
+        }
+        self.grab_cursor(world, grab);
+        Ok(())
+    }
+    fn shutdown(&mut self, world: &mut World) -> SystemResult {
+        self.grab_cursor(world, false);
+        Ok(())
+    }
+    fn name(&self) -> &str {
+        self.name
+    }
+    fn get_run_stage(&self) -> RunStage {
+        RunStage::Update
+    }
+}
+
+// Moves every entity with an OrbitCamera around its focus and points the Camera through the last one, every
+// frame in Update like the FlyCameraController
+pub struct OrbitCameraController {
+    name: &'static str,
+}
+
+impl OrbitCameraController {
+    pub fn new() -> Self {
+        Self {
+            name: "OrbitCameraController",
+        }
+    }
+}
+
+impl System for OrbitCameraController {
+    fn startup(&mut self, _world: &mut World) -> SystemResult {
+        Ok(())
+    }
+    fn run(&mut self, world: &mut World) -> SystemResult {
+        let input = ControllerInput::read(world)?;
+        let dt = world
+            .get_resource::<Time>()
+            .context("Camera controllers need the Time resource, add the CorePlugin")?
+            .raw_delta();
+
+        let mut camera_query = world
+            .query::<(&mut TransformComponent, &mut OrbitCamera)>()
+            .context("OrbitCameraController failed to query the orbit cameras")?;
+        let mut camera = world
+            .get_resource_mut::<Camera>()
+            .context("Camera controllers need the Camera resource")?;
+        for (transform, orbit_camera) in camera_query.iter() {
+            if !orbit_camera.initialized {
+                orbit_camera.look_from(transform);
+            }
+            let y_sign = if orbit_camera.invert_y { 1.0 } else { -1.0 };
+            let mut rotation = input.look * orbit_camera.look_speed * dt;
+            if input.orbit_rotate {
+                // The scene turns with the cursor, the camera swings the other way around the focus
+                rotation += Vector3::new(y_sign * input.mouse_delta.y, input.mouse_delta.x, 0.0)
+                    * orbit_camera.rotate_sensitivity;
+            }
+            orbit_camera.pitch += rotation.x;
+            orbit_camera.yaw += rotation.y;
+
+            let (pitch, yaw) = (orbit_camera.pitch, orbit_camera.yaw);
+            let mut pan = (right(yaw) * input.movement.x
+                + screen_up(pitch, yaw) * input.movement.y)
+                * orbit_camera.pan_speed
+                * dt;
+            if input.orbit_pan {
+                // The focus follows the cursor the other way, so the scene moves with it
+                pan += (-right(yaw) * input.mouse_delta.x
+                    + screen_up(pitch, yaw) * input.mouse_delta.y)
+                    * orbit_camera.pan_sensitivity;
+            }
+            orbit_camera.focus += pan * orbit_camera.distance;
+
+            let zoom = input.scroll * orbit_camera.zoom_sensitivity + input.movement.z * dt;
+            orbit_camera.distance *= (-zoom).exp();
+            orbit_camera.clamp();
+
+            transform.translation = orbit_camera.position();
+            transform.rotation = Vector3::new(orbit_camera.pitch, orbit_camera.yaw, 0.0);
+            camera.set_view_yxz(transform.translation, transform.rotation);
+        }
+        Ok(())
+    }
+    fn shutdown(&mut self, _world: &mut World) -> SystemResult {
+        Ok(())
+    }
+    fn name(&self) -> &str {
+        self.name
+    }
+    fn get_run_stage(&self) -> RunStage {
+        RunStage::Update
+    }
+}
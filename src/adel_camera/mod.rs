@@ -1,3 +1,5 @@
 mod camera;
+mod controller;
 
 pub use camera::*;
+pub use controller::*;
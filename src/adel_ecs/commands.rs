@@ -0,0 +1,84 @@
+use crate::adel_ecs::entity::Entities;
+use crate::adel_ecs::{Entity, World};
+use std::cell::RefMut;
+
+pub(crate) type Command = Box<dyn FnOnce(&mut World)>;
+
+// Systems usually hold Ref/RefMut borrows of component storages while iterating, which means they can't get the
+// &mut World needed to spawn or despawn. Commands records those structural changes instead, the Application
+// applies them with World::apply_commands between RunStages, once no system is running.
+// Commands are applied in the order they were pushed.
+pub struct Commands<'w> {
+    entities: &'w Entities,
+    queue: RefMut<'w, Vec<Command>>,
+}
+
+impl<'w> Commands<'w> {
+    pub(crate) fn new(entities: &'w Entities, queue: RefMut<'w, Vec<Command>>) -> Self {
+        Self { entities, queue }
+    }
+    // The entity handle is usable right away (to store in a component for example) but it won't be alive
+    // until the commands are applied
+    pub fn spawn(&mut self) -> EntityCommands<'_, 'w> {
+        let entity = self.entities.reserve();
+        EntityCommands {
+            entity,
+            commands: self,
+        }
+    }
+    pub fn entity(&mut self, entity: Entity) -> EntityCommands<'_, 'w> {
+        EntityCommands {
+            entity,
+            commands: self,
+        }
+    }
+    pub fn insert<ComponentType: 'static>(&mut self, entity: Entity, component: ComponentType) {
+        self.add(move |world| world.add_component_to_entity(entity, component));
+    }
+    pub fn remove<ComponentType: 'static>(&mut self, entity: Entity) {
+        self.add(move |world| {
+            world.remove_component::<ComponentType>(entity);
+        });
+    }
+    pub fn despawn(&mut self, entity: Entity) {
+        self.add(move |world| {
+            world.despawn(entity);
+        });
+    }
+    pub fn insert_resource<R: 'static>(&mut self, resource: R) {
+        self.add(move |world| world.insert_resource(resource));
+    }
+    // Anything else that needs the World mutably
+    pub fn add(&mut self, command: impl FnOnce(&mut World) + 'static) {
+        self.queue.push(Box::new(command));
+    }
+    pub fn len(&self) -> usize {
+        self.queue.len()
+    }
+    pub fn is_empty(&self) -> bool {
+        self.queue.is_empty()
+    }
+}
+
+// Commands for a single entity, lets inserts be chained after a spawn
+pub struct EntityCommands<'a, 'w> {
+    entity: Entity,
+    commands: &'a mut Commands<'w>,
+}
+
+impl<'a, 'w> EntityCommands<'a, 'w> {
+    pub fn id(&self) -> Entity {
+        self.entity
+    }
+    pub fn insert<ComponentType: 'static>(&mut self, component: ComponentType) -> &mut Self {
+        self.commands.insert(self.entity, component);
+        self
+    }
+    pub fn remove<ComponentType: 'static>(&mut self) -> &mut Self {
+        self.commands.remove::<ComponentType>(self.entity);
+        self
+    }
+    pub fn despawn(&mut self) {
+        self.commands.despawn(self.entity);
+    }
+}
@@ -0,0 +1,98 @@
+use std::cell::Cell;
+
+// An Entity is an index into the component storages plus the generation of that index. When an entity is
+// despawned its index is recycled for the next spawned entity and the generation is bumped, that way an old
+// handle can be detected as stale instead of silently pointing at whatever entity now lives in that slot.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct Entity {
+    index: usize,
+    generation: u32,
+}
+
+impl Entity {
+    pub(crate) fn new(index: usize, generation: u32) -> Self {
+        Self { index, generation }
+    }
+    pub fn index(&self) -> usize {
+        self.index
+    }
+    pub fn generation(&self) -> u32 {
+        self.generation
+    }
+}
+
+// Book keeping for every entity slot the World has handed out
+pub(crate) struct Entities {
+    generations: Vec<u32>,
+    alive: Vec<bool>,
+    free_indices: Vec<usize>,
+    // Indices handed out by reserve that don't have a slot yet, they sit right after the last slot
+    reserved: Cell<usize>,
+}
+
+impl Entities {
+    pub fn new() -> Self {
+        Self {
+            generations: Vec::new(),
+            alive: Vec::new(),
+            free_indices: Vec::new(),
+            reserved: Cell::new(0),
+        }
+    }
+    // Reuses a despawned index when one is available
+    pub fn allocate(&mut self) -> Entity {
+        self.flush();
+        if let Some(index) = self.free_indices.pop() {
+            self.alive[index] = true;
+            return Entity::new(index, self.generations[index]);
+        }
+        let index = self.generations.len();
+        self.generations.push(0);
+        self.alive.push(true);
+        Entity::new(index, 0)
+    }
+    // Hands out an entity without needing mutable access, so Commands can spawn while the World is borrowed.
+    // Reserved entities always get a fresh index and only become alive once flush is called
+    pub fn reserve(&self) -> Entity {
+        let index = self.generations.len() + self.reserved.get();
+        self.reserved.set(self.reserved.get() + 1);
+        Entity::new(index, 0)
+    }
+    // Gives every reserved entity its slot
+    pub fn flush(&mut self) {
+        let reserved = self.reserved.replace(0);
+        self.generations
+            .resize(self.generations.len() + reserved, 0);
+        self.alive.resize(self.alive.len() + reserved, true);
+    }
+    // Returns false if the entity was already despawned (stale handle)
+    pub fn free(&mut self, entity: Entity) -> bool {
+        if !self.is_alive(entity) {
+            return false;
+        }
+        self.alive[entity.index] = false;
+        // Wrapping is fine here, an index would need to be recycled 4 billion times to alias
+        self.generations[entity.index] = self.generations[entity.index].wrapping_add(1);
+        self.free_indices.push(entity.index);
+        true
+    }
+    pub fn is_alive(&self, entity: Entity) -> bool {
+        entity.index < self.generations.len()
+            && self.alive[entity.index]
+            && self.generations[entity.index] == entity.generation
+    }
+    // Current live entity stored at index, if any
+    pub fn entity_at(&self, index: usize) -> Option<Entity> {
+        match self.alive.get(index) {
+            Some(true) => Some(Entity::new(index, self.generations[index])),
+            _ => None,
+        }
+    }
+    // Total number of slots, alive or not
+    pub fn len(&self) -> usize {
+        self.generations.len()
+    }
+    pub fn iter(&self) -> impl Iterator<Item = Entity> + '_ {
+        (0..self.len()).filter_map(move |index| self.entity_at(index))
+    }
+}
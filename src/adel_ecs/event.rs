@@ -0,0 +1,126 @@
+use crate::adel_ecs::World;
+use std::cell::RefMut;
+use std::marker::PhantomData;
+
+// Events are stored as a World resource per event type. They are double buffered, update() is called once per
+// frame by the Application and drops the events of the frame before last, so every event lives for two frames.
+// That gives every system a full frame to see an event no matter when in the frame it was sent or when the
+// system runs. Readers keep their own cursor, so any number of systems can each read every event once.
+pub struct Events<T> {
+    previous: Vec<(usize, T)>,
+    current: Vec<(usize, T)>,
+    // Id handed to the next event sent, ids only ever go up
+    event_count: usize,
+}
+
+impl<T> Events<T> {
+    pub fn new() -> Self {
+        Self {
+            previous: Vec::new(),
+            current: Vec::new(),
+            event_count: 0,
+        }
+    }
+    pub fn send(&mut self, event: T) {
+        self.current.push((self.event_count, event));
+        self.event_count += 1;
+    }
+    // Swap the buffers, dropping everything sent before the previous update
+    pub fn update(&mut self) {
+        self.previous = std::mem::take(&mut self.current);
+    }
+    pub fn is_empty(&self) -> bool {
+        self.previous.is_empty() && self.current.is_empty()
+    }
+    // Every event still buffered, oldest first
+    pub fn iter(&self) -> impl Iterator<Item = &T> {
+        self.previous
+            .iter()
+            .chain(self.current.iter())
+            .map(|(_, event)| event)
+    }
+    // Reader that will see every event still buffered
+    pub fn get_reader(&self) -> EventReader<T> {
+        EventReader::new()
+    }
+    // Reader that only sees events sent from now on
+    pub fn get_reader_current(&self) -> EventReader<T> {
+        EventReader {
+            last_event_count: self.event_count,
+            _marker: PhantomData,
+        }
+    }
+}
+
+impl<T> Default for Events<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+// Cursor into an Events<T>, owned by whoever is reading
+pub struct EventReader<T> {
+    last_event_count: usize,
+    _marker: PhantomData<T>,
+}
+
+impl<T> EventReader<T> {
+    pub fn new() -> Self {
+        Self {
+            last_event_count: 0,
+            _marker: PhantomData,
+        }
+    }
+    // Events sent since this reader last read, events missed for more than a frame are gone
+    pub fn read<'a>(&mut self, events: &'a Events<T>) -> impl Iterator<Item = &'a T> {
+        let last_event_count = self.last_event_count;
+        self.last_event_count = events.event_count;
+        events
+            .previous
+            .iter()
+            .chain(events.current.iter())
+            .filter(move |(id, _)| *id >= last_event_count)
+            .map(|(_, event)| event)
+    }
+    // Marks everything as read without looking at it
+    pub fn clear(&mut self, events: &Events<T>) {
+        self.last_event_count = events.event_count;
+    }
+}
+
+impl<T> Default for EventReader<T> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+// Holds the Events<T> resource borrowed for sending
+pub struct EventWriter<'a, T> {
+    events: RefMut<'a, Events<T>>,
+}
+
+impl<'a, T> EventWriter<'a, T> {
+    pub fn send(&mut self, event: T) {
+        self.events.send(event);
+    }
+    pub fn send_batch(&mut self, events: impl IntoIterator<Item = T>) {
+        for event in events {
+            self.events.send(event);
+        }
+    }
+}
+
+impl<'a, T: 'static> EventWriter<'a, T> {
+    pub(crate) fn new(world: &'a World) -> Option<Self> {
+        world
+            .get_resource_mut::<Events<T>>()
+            .map(|events| Self { events })
+    }
+}
+
+// Registered by World::add_event, flips the buffers of a single event type
+pub(crate) fn update_events<T: 'static>(world: &World) {
+    if let Some(mut events) = world.get_resource_mut::<Events<T>>() {
+        events.update();
+    }
+}
@@ -1,5 +1,21 @@
+mod commands;
+mod entity;
+mod event;
+mod profiler;
+mod query;
+mod schedule;
+mod state;
+mod storage;
 mod system;
 mod world;
 
+pub use commands::{Commands, EntityCommands};
+pub use entity::Entity;
+pub use event::{EventReader, EventWriter, Events};
+pub use profiler::{Profiler, TimingStats};
+pub use query::*;
+pub use schedule::*;
+pub use state::{in_state, State, StateData};
+pub use storage::*;
 pub use system::*;
 pub use world::*;
@@ -0,0 +1,260 @@
+use crate::adel_ecs::RunStage;
+use std::collections::{HashMap, VecDeque};
+use std::fmt::Write as _;
+use std::path::PathBuf;
+use std::time::{Duration, Instant};
+
+// Min/avg/max over the last window samples of something that gets timed every frame
+#[derive(Debug, Clone)]
+pub struct TimingStats {
+    samples: VecDeque<Duration>,
+    window: usize,
+}
+
+impl TimingStats {
+    fn new(window: usize) -> Self {
+        Self {
+            samples: VecDeque::with_capacity(window),
+            window,
+        }
+    }
+    fn push(&mut self, duration: Duration) {
+        if self.samples.len() == self.window {
+            self.samples.pop_front();
+        }
+        self.samples.push_back(duration);
+    }
+    pub fn min(&self) -> Duration {
+        self.samples.iter().min().copied().unwrap_or_default()
+    }
+    pub fn max(&self) -> Duration {
+        self.samples.iter().max().copied().unwrap_or_default()
+    }
+    pub fn average(&self) -> Duration {
+        if self.samples.is_empty() {
+            return Duration::ZERO;
+        }
+        self.samples.iter().sum::<Duration>() / self.samples.len() as u32
+    }
+    pub fn last(&self) -> Duration {
+        self.samples.back().copied().unwrap_or_default()
+    }
+    // Number of samples currently in the window
+    pub fn len(&self) -> usize {
+        self.samples.len()
+    }
+    pub fn is_empty(&self) -> bool {
+        self.samples.is_empty()
+    }
+}
+
+// A finished span in the Chrome trace_event format, times are in microseconds since the Profiler was created
+struct TraceEvent {
+    name: String,
+    category: &'static str,
+    start: f64,
+    duration: f64,
+    frame: u64,
+}
+
+// Resource timing every system the Schedule runs. Insert it to turn profiling on, the Schedule doesn't time
+// anything when it's missing. With a trace path set every span (startup, each system of each stage of every
+// frame, shutdown) is also kept and written out as Chrome trace_event JSON when the Application shuts down,
+// which can be opened in chrome://tracing or ui.perfetto.dev. That grows for as long as the Application runs so
+// it's meant for profiling sessions, not for leaving on.
+pub struct Profiler {
+    window: usize,
+    systems: HashMap<(RunStage, String), TimingStats>,
+    stages: HashMap<RunStage, TimingStats>,
+    frames: TimingStats,
+    origin: Instant,
+    frame: u64,
+    frame_start: Instant,
+    trace_path: Option<PathBuf>,
+    trace: Vec<TraceEvent>,
+}
+
+impl Profiler {
+    pub const DEFAULT_WINDOW: usize = 120;
+
+    pub fn new() -> Self {
+        let now = Instant::now();
+        Self {
+            window: Self::DEFAULT_WINDOW,
+            systems: HashMap::new(),
+            stages: HashMap::new(),
+            frames: TimingStats::new(Self::DEFAULT_WINDOW),
+            origin: now,
+            frame: 0,
+            frame_start: now,
+            trace_path: None,
+            trace: Vec::new(),
+        }
+    }
+    // Number of frames the rolling statistics cover
+    pub fn with_window(mut self, window: usize) -> Self {
+        self.window = window.max(1);
+        self.frames = TimingStats::new(self.window);
+        self
+    }
+    pub fn with_chrome_trace(mut self, path: impl Into<PathBuf>) -> Self {
+        self.trace_path = Some(path.into());
+        self
+    }
+    pub fn system_stats(&self, stage: RunStage, label: &str) -> Option<&TimingStats> {
+        self.systems.get(&(stage, label.to_owned()))
+    }
+    pub fn stage_stats(&self, stage: RunStage) -> Option<&TimingStats> {
+        self.stages.get(&stage)
+    }
+    pub fn frame_stats(&self) -> &TimingStats {
+        &self.frames
+    }
+    // Frames finished since the Profiler was inserted
+    pub fn frame(&self) -> u64 {
+        self.frame
+    }
+    // One line for the frame, then every stage followed by its systems, for logging
+    pub fn report(&self) -> String {
+        let mut report = String::new();
+        let mut line = |name: &str, stats: &TimingStats| {
+            let _ = writeln!(
+                report,
+                "{:<32} min {:>8.3}ms avg {:>8.3}ms max {:>8.3}ms",
+                name,
+                stats.min().as_secs_f64() * 1000.0,
+                stats.average().as_secs_f64() * 1000.0,
+                stats.max().as_secs_f64() * 1000.0
+            );
+        };
+        line("Frame", &self.frames);
+        for stage in RunStage::ALL {
+            if let Some(stats) = self.stages.get(&stage) {
+                line(&format!("{:?}", stage), stats);
+            }
+            let mut systems: Vec<(&String, &TimingStats)> = self
+                .systems
+                .iter()
+                .filter(|((system_stage, _), _)| *system_stage == stage)
+                .map(|((_, label), stats)| (label, stats))
+                .collect();
+            systems.sort_by(|a, b| a.0.cmp(b.0));
+            for (label, stats) in systems {
+                line(&format!("  {}", label), stats);
+            }
+        }
+        report
+    }
+
+    pub(crate) fn record_system(
+        &mut self,
+        stage: RunStage,
+        label: &str,
+        start: Instant,
+        duration: Duration,
+    ) {
+        let window = self.window;
+        self.systems
+            .entry((stage, label.to_owned()))
+            .or_insert_with(|| TimingStats::new(window))
+            .push(duration);
+        self.record_span(label, "system", start, duration);
+    }
+    pub(crate) fn record_stage(&mut self, stage: RunStage, start: Instant, duration: Duration) {
+        let window = self.window;
+        self.stages
+            .entry(stage)
+            .or_insert_with(|| TimingStats::new(window))
+            .push(duration);
+        self.record_span(&format!("{:?}", stage), "stage", start, duration);
+    }
+    // Trace only spans, startup and shutdown don't need statistics
+    pub(crate) fn record_span(
+        &mut self,
+        name: &str,
+        category: &'static str,
+        start: Instant,
+        duration: Duration,
+    ) {
+        if self.trace_path.is_none() {
+            return;
+        }
+        self.trace.push(TraceEvent {
+            name: name.to_owned(),
+            category,
+            start: start.saturating_duration_since(self.origin).as_secs_f64() * 1_000_000.0,
+            duration: duration.as_secs_f64() * 1_000_000.0,
+            frame: self.frame,
+        });
+    }
+    // The first frame starts once startup is done, not when the Profiler was created
+    pub(crate) fn start_first_frame(&mut self) {
+        self.frame_start = Instant::now();
+    }
+    // Called by the Application at the end of every frame
+    pub fn end_frame(&mut self) {
+        let duration = self.frame_start.elapsed();
+        self.frames.push(duration);
+        self.record_span("Frame", "frame", self.frame_start, duration);
+        self.frame += 1;
+        self.frame_start = Instant::now();
+    }
+    // Writes the trace to the path given to with_chrome_trace, does nothing without one
+    pub fn write_chrome_trace(&self) -> std::io::Result<()> {
+        let path = match &self.trace_path {
+            Some(path) => path,
+            None => return Ok(()),
+        };
+        std::fs::write(path, self.chrome_trace_json())?;
+        log::info!(
+            "Wrote {} trace events to {}",
+            self.trace.len(),
+            path.display()
+        );
+        Ok(())
+    }
+    // Written by hand, the format is simple enough that it's not worth pulling in serde
+    pub fn chrome_trace_json(&self) -> String {
+        let mut json = String::from("{\"displayTimeUnit\":\"ms\",\"traceEvents\":[");
+        for (index, event) in self.trace.iter().enumerate() {
+            if index > 0 {
+                json.push(',');
+            }
+            let _ = write!(
+                json,
+                "{{\"name\":\"{}\",\"cat\":\"{}\",\"ph\":\"X\",\"ts\":{:.3},\"dur\":{:.3},\"pid\":1,\"tid\":1,\"args\":{{\"frame\":{}}}}}",
+                escape_json(&event.name),
+                event.category,
+                event.start,
+                event.duration,
+                event.frame
+            );
+        }
+        json.push_str("]}");
+        json
+    }
+}
+
+impl Default for Profiler {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+fn escape_json(value: &str) -> String {
+    let mut escaped = String::with_capacity(value.len());
+    for character in value.chars() {
+        match character {
+            '"' => escaped.push_str("\\\""),
+            '\\' => escaped.push_str("\\\\"),
+            '\n' => escaped.push_str("\\n"),
+            '\r' => escaped.push_str("\\r"),
+            '\t' => escaped.push_str("\\t"),
+            character if (character as u32) < 0x20 => {
+                let _ = write!(escaped, "\\u{:04x}", character as u32);
+            }
+            character => escaped.push(character),
+        }
+    }
+    escaped
+}
@@ -0,0 +1,749 @@
+use crate::adel_ecs::{ComponentStorage, Entity, World};
+use std::any::{type_name, TypeId};
+use std::cell::{Ref, RefMut};
+use std::fmt;
+use std::marker::PhantomData;
+
+// Queries replace the hand written borrow_component + index matching that every system was doing. A query is
+// built from a tuple of component references and yields only the entities that have all of them, e.g.
+//
+//     let mut query = world.query::<(&mut TransformComponent, &ModelComponent)>()?;
+//     for (transform, model) in query.iter() { ... }
+//
+// Every storage the query touches is borrowed when the query is constructed, so a query that would alias a
+// component (the same type twice with one of them mutable) or a storage that is already borrowed by someone
+// else is reported as a QueryError up front instead of panicking inside of RefCell halfway through a system.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum QueryError {
+    // The query itself asks for the same component mutably more than once, or mutably and immutably
+    ConflictingAccess(&'static str),
+    // The component storage is already borrowed outside of this query
+    AlreadyBorrowed(&'static str),
+}
+
+impl fmt::Display for QueryError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            QueryError::ConflictingAccess(name) => {
+                write!(f, "Query requests conflicting access to component {}", name)
+            }
+            QueryError::AlreadyBorrowed(name) => {
+                write!(f, "Component {} is already borrowed elsewhere", name)
+            }
+        }
+    }
+}
+
+impl std::error::Error for QueryError {}
+
+// Tracks which component types a query reads and writes so conflicts are caught before anything is borrowed
+#[derive(Default)]
+pub struct Access {
+    reads: Vec<TypeId>,
+    writes: Vec<TypeId>,
+}
+
+impl Access {
+    pub fn add_read<T: 'static>(&mut self) -> Result<(), QueryError> {
+        let type_id = TypeId::of::<T>();
+        if self.writes.contains(&type_id) {
+            return Err(QueryError::ConflictingAccess(type_name::<T>()));
+        }
+        self.reads.push(type_id);
+        Ok(())
+    }
+    pub fn add_write<T: 'static>(&mut self) -> Result<(), QueryError> {
+        let type_id = TypeId::of::<T>();
+        if self.writes.contains(&type_id) || self.reads.contains(&type_id) {
+            return Err(QueryError::ConflictingAccess(type_name::<T>()));
+        }
+        self.writes.push(type_id);
+        Ok(())
+    }
+}
+
+// Implemented for everything that can be requested from a query: &T, &mut T, Option<Q>, Entity and tuples of those.
+// State holds the RefCell guards for the lifetime of the Query, Fetch is the view of that state used while
+// iterating and Item is what the iterator hands out per entity.
+pub trait QueryData {
+    type State<'w>;
+    type Fetch<'q>;
+    type Item<'q>;
+
+    fn access(access: &mut Access) -> Result<(), QueryError>;
+    fn borrow(world: &World) -> Result<Self::State<'_>, QueryError>;
+    fn fetch<'q>(state: &'q mut Self::State<'_>) -> Self::Fetch<'q>;
+    fn matches(fetch: &Self::Fetch<'_>, entity: Entity) -> bool;
+    // The entities that can possibly match, used to drive iteration from the smallest storage in the query.
+    // None means the parameter doesn't restrict anything (Option<&T>, Entity)
+    fn entities<'q>(fetch: &Self::Fetch<'q>) -> Option<&'q [Entity]>;
+    // Safety: entity must match, and every entity may only be fetched once per Fetch as &mut T items of
+    // different entities are handed out at the same time
+    unsafe fn get<'q>(fetch: &mut Self::Fetch<'q>, entity: Entity) -> Self::Item<'q>;
+}
+
+// Filters restrict which entities match without fetching any data
+pub trait QueryFilter {
+    type State<'w>;
+
+    fn access(access: &mut Access) -> Result<(), QueryError>;
+    fn borrow(world: &World) -> Result<Self::State<'_>, QueryError>;
+    fn matches(state: &Self::State<'_>, entity: Entity) -> bool;
+}
+
+// Only matches entities that have a T
+pub struct With<T>(PhantomData<T>);
+// Only matches entities that do not have a T
+pub struct Without<T>(PhantomData<T>);
+// Only matches entities whose T was added since the running system last ran
+pub struct Added<T>(PhantomData<T>);
+// Only matches entities whose T was added or mutably accessed since the running system last ran.
+// These read T, so they can't be combined with &mut T of the same type in one query
+pub struct Changed<T>(PhantomData<T>);
+
+fn try_borrow<T: 'static>(
+    world: &World,
+) -> Result<Option<Ref<'_, ComponentStorage<T>>>, QueryError> {
+    match world.component_cell::<T>() {
+        Some(cell) => match cell.try_borrow() {
+            Ok(storage) => Ok(Some(storage)),
+            Err(_) => Err(QueryError::AlreadyBorrowed(type_name::<T>())),
+        },
+        None => Ok(None),
+    }
+}
+
+fn try_borrow_mut<T: 'static>(
+    world: &World,
+) -> Result<Option<RefMut<'_, ComponentStorage<T>>>, QueryError> {
+    match world.component_cell::<T>() {
+        Some(cell) => match cell.try_borrow_mut() {
+            Ok(mut storage) => {
+                storage.set_change_tick(world.change_tick());
+                Ok(Some(storage))
+            }
+            Err(_) => Err(QueryError::AlreadyBorrowed(type_name::<T>())),
+        },
+        None => Ok(None),
+    }
+}
+
+impl<'a, T: 'static> QueryData for &'a T {
+    // None when no entity has ever had this component
+    type State<'w> = Option<Ref<'w, ComponentStorage<T>>>;
+    type Fetch<'q> = Option<&'q ComponentStorage<T>>;
+    type Item<'q> = &'q T;
+
+    fn access(access: &mut Access) -> Result<(), QueryError> {
+        access.add_read::<T>()
+    }
+    fn borrow(world: &World) -> Result<Self::State<'_>, QueryError> {
+        try_borrow::<T>(world)
+    }
+    fn fetch<'q>(state: &'q mut Self::State<'_>) -> Self::Fetch<'q> {
+        state.as_deref()
+    }
+    fn matches(fetch: &Self::Fetch<'_>, entity: Entity) -> bool {
+        fetch.map_or(false, |storage| storage.contains(entity))
+    }
+    fn entities<'q>(fetch: &Self::Fetch<'q>) -> Option<&'q [Entity]> {
+        // A missing storage matches nothing
+        Some(fetch.map_or(&[], |storage| storage.entities()))
+    }
+    unsafe fn get<'q>(fetch: &mut Self::Fetch<'q>, entity: Entity) -> Self::Item<'q> {
+        let storage: &'q ComponentStorage<T> = fetch.unwrap();
+        storage.get(entity).unwrap()
+    }
+}
+
+// Mutable fetches hold a raw pointer to the storage slots so that &mut T for different entities can be alive
+// at the same time, the same way slice::IterMut does it. The RefMut guard in the State keeps the storage
+// exclusively borrowed for as long as any of these references exist.
+pub struct FetchMut<'q, T> {
+    storage: *mut ComponentStorage<T>,
+    _marker: PhantomData<&'q mut T>,
+}
+
+impl<'a, T: 'static> QueryData for &'a mut T {
+    type State<'w> = Option<RefMut<'w, ComponentStorage<T>>>;
+    type Fetch<'q> = Option<FetchMut<'q, T>>;
+    type Item<'q> = &'q mut T;
+
+    fn access(access: &mut Access) -> Result<(), QueryError> {
+        access.add_write::<T>()
+    }
+    fn borrow(world: &World) -> Result<Self::State<'_>, QueryError> {
+        try_borrow_mut::<T>(world)
+    }
+    fn fetch<'q>(state: &'q mut Self::State<'_>) -> Self::Fetch<'q> {
+        state.as_deref_mut().map(|storage| FetchMut {
+            storage: storage as *mut ComponentStorage<T>,
+            _marker: PhantomData,
+        })
+    }
+    fn matches(fetch: &Self::Fetch<'_>, entity: Entity) -> bool {
+        match fetch {
+            // Safety: only reads sparse and the entity list, never a component
+            Some(fetch) => unsafe { ComponentStorage::contains_raw(fetch.storage, entity) },
+            None => false,
+        }
+    }
+    fn entities<'q>(fetch: &Self::Fetch<'q>) -> Option<&'q [Entity]> {
+        match fetch {
+            // Safety: the entity list is never modified while the storage is borrowed by the query
+            Some(fetch) => Some(unsafe { ComponentStorage::entities_raw(fetch.storage) }),
+            None => Some(&[]),
+        }
+    }
+    unsafe fn get<'q>(fetch: &mut Self::Fetch<'q>, entity: Entity) -> Self::Item<'q> {
+        let fetch = fetch.as_ref().unwrap();
+        &mut *ComponentStorage::get_raw_mut(fetch.storage, entity).unwrap()
+    }
+}
+
+impl<Q: QueryData> QueryData for Option<Q> {
+    type State<'w> = Q::State<'w>;
+    type Fetch<'q> = Q::Fetch<'q>;
+    type Item<'q> = Option<Q::Item<'q>>;
+
+    fn access(access: &mut Access) -> Result<(), QueryError> {
+        Q::access(access)
+    }
+    fn borrow(world: &World) -> Result<Self::State<'_>, QueryError> {
+        Q::borrow(world)
+    }
+    fn fetch<'q>(state: &'q mut Self::State<'_>) -> Self::Fetch<'q> {
+        Q::fetch(state)
+    }
+    fn matches(_fetch: &Self::Fetch<'_>, _entity: Entity) -> bool {
+        true
+    }
+    fn entities<'q>(_fetch: &Self::Fetch<'q>) -> Option<&'q [Entity]> {
+        None
+    }
+    unsafe fn get<'q>(fetch: &mut Self::Fetch<'q>, entity: Entity) -> Self::Item<'q> {
+        if Q::matches(fetch, entity) {
+            Some(Q::get(fetch, entity))
+        } else {
+            None
+        }
+    }
+}
+
+impl QueryData for Entity {
+    type State<'w> = ();
+    type Fetch<'q> = ();
+    type Item<'q> = Entity;
+
+    fn access(_access: &mut Access) -> Result<(), QueryError> {
+        Ok(())
+    }
+    fn borrow(_world: &World) -> Result<Self::State<'_>, QueryError> {
+        Ok(())
+    }
+    fn fetch<'q>(_state: &'q mut Self::State<'_>) -> Self::Fetch<'q> {}
+    fn matches(_fetch: &Self::Fetch<'_>, _entity: Entity) -> bool {
+        true
+    }
+    fn entities<'q>(_fetch: &Self::Fetch<'q>) -> Option<&'q [Entity]> {
+        None
+    }
+    unsafe fn get<'q>(_fetch: &mut Self::Fetch<'q>, entity: Entity) -> Self::Item<'q> {
+        entity
+    }
+}
+
+impl QueryFilter for () {
+    type State<'w> = ();
+
+    fn access(_access: &mut Access) -> Result<(), QueryError> {
+        Ok(())
+    }
+    fn borrow(_world: &World) -> Result<Self::State<'_>, QueryError> {
+        Ok(())
+    }
+    fn matches(_state: &Self::State<'_>, _entity: Entity) -> bool {
+        true
+    }
+}
+
+impl<T: 'static> QueryFilter for With<T> {
+    type State<'w> = Option<Ref<'w, ComponentStorage<T>>>;
+
+    fn access(access: &mut Access) -> Result<(), QueryError> {
+        access.add_read::<T>()
+    }
+    fn borrow(world: &World) -> Result<Self::State<'_>, QueryError> {
+        try_borrow::<T>(world)
+    }
+    fn matches(state: &Self::State<'_>, entity: Entity) -> bool {
+        state
+            .as_ref()
+            .map_or(false, |storage| storage.contains(entity))
+    }
+}
+
+impl<T: 'static> QueryFilter for Without<T> {
+    type State<'w> = Option<Ref<'w, ComponentStorage<T>>>;
+
+    fn access(access: &mut Access) -> Result<(), QueryError> {
+        access.add_read::<T>()
+    }
+    fn borrow(world: &World) -> Result<Self::State<'_>, QueryError> {
+        try_borrow::<T>(world)
+    }
+    fn matches(state: &Self::State<'_>, entity: Entity) -> bool {
+        !state
+            .as_ref()
+            .map_or(false, |storage| storage.contains(entity))
+    }
+}
+
+impl<T: 'static> QueryFilter for Added<T> {
+    type State<'w> = (Option<Ref<'w, ComponentStorage<T>>>, u64);
+
+    fn access(access: &mut Access) -> Result<(), QueryError> {
+        access.add_read::<T>()
+    }
+    fn borrow(world: &World) -> Result<Self::State<'_>, QueryError> {
+        Ok((try_borrow::<T>(world)?, world.last_change_tick()))
+    }
+    fn matches(state: &Self::State<'_>, entity: Entity) -> bool {
+        let (storage, last_run) = state;
+        storage
+            .as_ref()
+            .map_or(false, |storage| storage.is_added(entity, *last_run))
+    }
+}
+
+impl<T: 'static> QueryFilter for Changed<T> {
+    type State<'w> = (Option<Ref<'w, ComponentStorage<T>>>, u64);
+
+    fn access(access: &mut Access) -> Result<(), QueryError> {
+        access.add_read::<T>()
+    }
+    fn borrow(world: &World) -> Result<Self::State<'_>, QueryError> {
+        Ok((try_borrow::<T>(world)?, world.last_change_tick()))
+    }
+    fn matches(state: &Self::State<'_>, entity: Entity) -> bool {
+        let (storage, last_run) = state;
+        storage
+            .as_ref()
+            .map_or(false, |storage| storage.is_changed(entity, *last_run))
+    }
+}
+
+macro_rules! impl_query_tuple {
+    ($(($name:ident, $index:tt)),*) => {
+        impl<$($name: QueryData),*> QueryData for ($($name,)*) {
+            type State<'w> = ($($name::State<'w>,)*);
+            type Fetch<'q> = ($($name::Fetch<'q>,)*);
+            type Item<'q> = ($($name::Item<'q>,)*);
+
+            fn access(access: &mut Access) -> Result<(), QueryError> {
+                $($name::access(access)?;)*
+                Ok(())
+            }
+            fn borrow(world: &World) -> Result<Self::State<'_>, QueryError> {
+                Ok(($($name::borrow(world)?,)*))
+            }
+            fn fetch<'q>(state: &'q mut Self::State<'_>) -> Self::Fetch<'q> {
+                ($($name::fetch(&mut state.$index),)*)
+            }
+            fn matches(fetch: &Self::Fetch<'_>, entity: Entity) -> bool {
+                $($name::matches(&fetch.$index, entity) &&)* true
+            }
+            fn entities<'q>(fetch: &Self::Fetch<'q>) -> Option<&'q [Entity]> {
+                let mut smallest: Option<&'q [Entity]> = None;
+                $(
+                    if let Some(entities) = $name::entities(&fetch.$index) {
+                        if smallest.map_or(true, |smallest| entities.len() < smallest.len()) {
+                            smallest = Some(entities);
+                        }
+                    }
+                )*
+                smallest
+            }
+            unsafe fn get<'q>(fetch: &mut Self::Fetch<'q>, entity: Entity) -> Self::Item<'q> {
+                ($($name::get(&mut fetch.$index, entity),)*)
+            }
+        }
+
+        impl<$($name: QueryFilter),*> QueryFilter for ($($name,)*) {
+            type State<'w> = ($($name::State<'w>,)*);
+
+            fn access(access: &mut Access) -> Result<(), QueryError> {
+                $($name::access(access)?;)*
+                Ok(())
+            }
+            fn borrow(world: &World) -> Result<Self::State<'_>, QueryError> {
+                Ok(($($name::borrow(world)?,)*))
+            }
+            fn matches(state: &Self::State<'_>, entity: Entity) -> bool {
+                $($name::matches(&state.$index, entity) &&)* true
+            }
+        }
+    };
+}
+
+impl_query_tuple!((A, 0));
+impl_query_tuple!((A, 0), (B, 1));
+impl_query_tuple!((A, 0), (B, 1), (C, 2));
+impl_query_tuple!((A, 0), (B, 1), (C, 2), (D, 3));
+impl_query_tuple!((A, 0), (B, 1), (C, 2), (D, 3), (E, 4));
+impl_query_tuple!((A, 0), (B, 1), (C, 2), (D, 3), (E, 4), (F, 5));
+impl_query_tuple!((A, 0), (B, 1), (C, 2), (D, 3), (E, 4), (F, 5), (G, 6));
+impl_query_tuple!(
+    (A, 0),
+    (B, 1),
+    (C, 2),
+    (D, 3),
+    (E, 4),
+    (F, 5),
+    (G, 6),
+    (H, 7)
+);
+
+pub struct Query<'w, Q: QueryData, F: QueryFilter = ()> {
+    world: &'w World,
+    state: Q::State<'w>,
+    filter: F::State<'w>,
+}
+
+impl<'w, Q: QueryData, F: QueryFilter> Query<'w, Q, F> {
+    pub(crate) fn new(world: &'w World) -> Result<Self, QueryError> {
+        let mut access = Access::default();
+        Q::access(&mut access)?;
+        F::access(&mut access)?;
+        Ok(Self {
+            world,
+            state: Q::borrow(world)?,
+            filter: F::borrow(world)?,
+        })
+    }
+    // The guards live inside of the Query, so even read only iteration needs &mut self
+    pub fn iter(&mut self) -> QueryIter<'_, 'w, Q, F> {
+        let fetch = Q::fetch(&mut self.state);
+        // Walk the smallest storage in the query, only fall back to every live entity when all of the
+        // requested components are optional
+        let entities = match Q::entities(&fetch) {
+            Some(entities) => QueryEntities::Storage(entities.iter()),
+            None => QueryEntities::World(self.world.entities().into_iter()),
+        };
+        QueryIter {
+            fetch,
+            filter: &self.filter,
+            entities,
+        }
+    }
+    pub fn get(&mut self, entity: Entity) -> Option<Q::Item<'_>> {
+        if !self.world.is_alive(entity) || !F::matches(&self.filter, entity) {
+            return None;
+        }
+        let mut fetch = Q::fetch(&mut self.state);
+        if Q::matches(&fetch, entity) {
+            // Safety: a single entity is fetched once and the Fetch is dropped with this call
+            Some(unsafe { Q::get(&mut fetch, entity) })
+        } else {
+            None
+        }
+    }
+    pub fn is_empty(&mut self) -> bool {
+        self.iter().next().is_none()
+    }
+}
+
+impl<'q, 'w, Q: QueryData, F: QueryFilter> IntoIterator for &'q mut Query<'w, Q, F> {
+    type Item = Q::Item<'q>;
+    type IntoIter = QueryIter<'q, 'w, Q, F>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+pub struct QueryIter<'q, 'w, Q: QueryData, F: QueryFilter> {
+    fetch: Q::Fetch<'q>,
+    filter: &'q F::State<'w>,
+    entities: QueryEntities<'q>,
+}
+
+enum QueryEntities<'q> {
+    Storage(std::slice::Iter<'q, Entity>),
+    World(std::vec::IntoIter<Entity>),
+}
+
+impl<'q> Iterator for QueryEntities<'q> {
+    type Item = Entity;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        match self {
+            QueryEntities::Storage(entities) => entities.next().copied(),
+            QueryEntities::World(entities) => entities.next(),
+        }
+    }
+}
+
+impl<'q, 'w, Q: QueryData, F: QueryFilter> Iterator for QueryIter<'q, 'w, Q, F> {
+    type Item = Q::Item<'q>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        for entity in self.entities.by_ref() {
+            if Q::matches(&self.fetch, entity) && F::matches(self.filter, entity) {
+                // Safety: an entity appears at most once in a storage or in the World's entity list
+                return Some(unsafe { Q::get(&mut self.fetch, entity) });
+            }
+        }
+        None
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[derive(Debug, Clone, Copy, PartialEq)]
+    struct Position(i32);
+    #[derive(Debug, Clone, Copy, PartialEq)]
+    struct Velocity(i32);
+
+    fn world_with(positions: &[i32]) -> (World, Vec<Entity>) {
+        let mut world = World::new();
+        let entities = positions
+            .iter()
+            .map(|position| {
+                let entity = world.new_entity();
+                world.add_component_to_entity(entity, Position(*position));
+                entity
+            })
+            .collect();
+        (world, entities)
+    }
+
+    fn positions(world: &World) -> Vec<(Entity, i32)> {
+        let mut query = world.query::<(Entity, &Position)>().unwrap();
+        query
+            .iter()
+            .map(|(entity, position)| (entity, position.0))
+            .collect()
+    }
+
+    #[test]
+    fn aliasing_queries_are_rejected() {
+        let (world, _) = world_with(&[1]);
+        let conflict = Some(QueryError::ConflictingAccess(type_name::<Position>()));
+        assert_eq!(world.query::<(&mut Position, &Position)>().err(), conflict);
+        assert_eq!(world.query::<(&Position, &mut Position)>().err(), conflict);
+        assert_eq!(
+            world.query::<(&mut Position, &mut Position)>().err(),
+            conflict
+        );
+        assert_eq!(
+            world.query::<(&mut Position, Option<&Position>)>().err(),
+            conflict
+        );
+        assert_eq!(
+            world
+                .query_filtered::<&mut Position, With<Position>>()
+                .err(),
+            conflict
+        );
+        // Reading the same component twice is fine
+        assert!(world.query::<(&Position, &Position)>().is_ok());
+    }
+
+    #[test]
+    fn borrowed_storages_are_reported() {
+        let (world, _) = world_with(&[1]);
+        let borrowed = Some(QueryError::AlreadyBorrowed(type_name::<Position>()));
+        {
+            let _writing = world.query::<&mut Position>().unwrap();
+            assert_eq!(world.query::<&Position>().err(), borrowed);
+            assert_eq!(world.query::<&mut Position>().err(), borrowed);
+            assert_eq!(
+                world.query_filtered::<Entity, Without<Position>>().err(),
+                borrowed
+            );
+        }
+        {
+            let _reading = world.borrow_component::<Position>().unwrap();
+            assert!(world.query::<&Position>().is_ok());
+            assert_eq!(world.query::<&mut Position>().err(), borrowed);
+        }
+        // Both guards are gone again
+        assert!(world.query::<&mut Position>().is_ok());
+    }
+
+    #[test]
+    fn mutable_items_of_every_entity_can_be_held_at_once() {
+        let (world, entities) = world_with(&[1, 2, 3]);
+        {
+            let mut query = world.query::<&mut Position>().unwrap();
+            let mut items: Vec<&mut Position> = query.iter().collect();
+            assert_eq!(items.len(), 3);
+            for item in items.iter_mut() {
+                item.0 *= 10;
+            }
+        }
+        assert_eq!(
+            positions(&world),
+            vec![(entities[0], 10), (entities[1], 20), (entities[2], 30)]
+        );
+    }
+
+    #[test]
+    fn stale_handles_match_nothing() {
+        let (mut world, entities) = world_with(&[1, 2]);
+        let stale = entities[0];
+        assert!(world.despawn(stale));
+        assert!(!world.despawn(stale));
+        // The new entity gets the despawned index with the next generation
+        let recycled = world.new_entity();
+        assert_eq!(recycled.index(), stale.index());
+        assert_ne!(recycled, stale);
+        world.add_component_to_entity(recycled, Position(3));
+
+        let mut query = world.query::<&mut Position>().unwrap();
+        assert!(query.get(stale).is_none());
+        assert_eq!(query.get(recycled).map(|position| position.0), Some(3));
+        drop(query);
+        assert!(world.get_component::<Position>(stale).is_none());
+        assert_eq!(world.has_component::<Position>(stale), Ok(false));
+        assert_eq!(world.component_names(stale), Ok(Vec::new()));
+        assert!(world.remove_component::<Position>(stale).is_none());
+        // Writes through a stale handle are dropped instead of landing on the recycled entity
+        world.add_component_to_entity(stale, Position(4));
+        world.add_component_to_entity(stale, Velocity(4));
+        assert_eq!(positions(&world), vec![(entities[1], 2), (recycled, 3)]);
+        assert!(world.query::<&Velocity>().unwrap().is_empty());
+    }
+
+    #[test]
+    fn iteration_after_swap_remove() {
+        let (mut world, entities) = world_with(&[1, 2, 3, 4]);
+        world.add_component_to_entity(entities[1], Velocity(20));
+        world.add_component_to_entity(entities[3], Velocity(40));
+        // The last Position is moved into the hole left by the first
+        assert_eq!(
+            world.remove_component::<Position>(entities[0]),
+            Some(Position(1))
+        );
+        assert_eq!(
+            positions(&world),
+            vec![(entities[3], 4), (entities[1], 2), (entities[2], 3)]
+        );
+        {
+            let mut query = world.query::<(&mut Position, &Velocity)>().unwrap();
+            for (position, velocity) in query.iter() {
+                position.0 += velocity.0;
+            }
+            assert_eq!(
+                query.get(entities[3]).map(|(position, _)| position.0),
+                Some(44)
+            );
+            assert!(query.get(entities[0]).is_none());
+        }
+        world.despawn(entities[3]);
+        assert_eq!(positions(&world), vec![(entities[2], 3), (entities[1], 22)]);
+        let mut query = world.query::<(Entity, Option<&Velocity>)>().unwrap();
+        let velocities: Vec<(Entity, Option<i32>)> = query
+            .iter()
+            .map(|(entity, velocity)| (entity, velocity.map(|velocity| velocity.0)))
+            .collect();
+        assert_eq!(
+            velocities,
+            vec![
+                (entities[0], None),
+                (entities[1], Some(20)),
+                (entities[2], None)
+            ]
+        );
+    }
+
+    struct Marker;
+
+    // a has a Position and a Velocity, b only a Position, c only a Velocity and d nothing
+    fn mixed_world() -> (World, [Entity; 4]) {
+        let mut world = World::new();
+        let entities = [
+            world.new_entity(),
+            world.new_entity(),
+            world.new_entity(),
+            world.new_entity(),
+        ];
+        world.add_component_to_entity(entities[0], Position(1));
+        world.add_component_to_entity(entities[0], Velocity(10));
+        world.add_component_to_entity(entities[1], Position(2));
+        world.add_component_to_entity(entities[2], Velocity(30));
+        (world, entities)
+    }
+
+    fn matching<F: QueryFilter>(world: &World) -> Vec<Entity> {
+        let mut query = world.query_filtered::<Entity, F>().unwrap();
+        query.iter().collect()
+    }
+
+    #[test]
+    fn with_and_without_filters() {
+        let (world, [a, b, c, d]) = mixed_world();
+        assert_eq!(matching::<With<Velocity>>(&world), vec![a, c]);
+        assert_eq!(matching::<Without<Velocity>>(&world), vec![b, d]);
+        assert_eq!(
+            matching::<(With<Position>, Without<Velocity>)>(&world),
+            vec![b]
+        );
+        // A component no entity ever had
+        assert!(matching::<With<Marker>>(&world).is_empty());
+        assert_eq!(matching::<Without<Marker>>(&world), vec![a, b, c, d]);
+
+        let mut query = world
+            .query_filtered::<&Position, Without<Velocity>>()
+            .unwrap();
+        assert_eq!(
+            query.iter().map(|position| position.0).collect::<Vec<_>>(),
+            vec![2]
+        );
+        assert!(query.get(a).is_none());
+        assert_eq!(query.get(b).map(|position| position.0), Some(2));
+    }
+
+    #[test]
+    fn optional_components() {
+        let (world, [a, b, c, d]) = mixed_world();
+        {
+            // Optional on its own doesn't narrow anything, every live entity is walked
+            let mut query = world.query::<(Entity, Option<&Velocity>)>().unwrap();
+            let velocities: Vec<(Entity, Option<i32>)> = query
+                .iter()
+                .map(|(entity, velocity)| (entity, velocity.map(|velocity| velocity.0)))
+                .collect();
+            assert_eq!(
+                velocities,
+                vec![(a, Some(10)), (b, None), (c, Some(30)), (d, None)]
+            );
+        }
+        {
+            let mut query = world.query::<(&Position, Option<&mut Velocity>)>().unwrap();
+            for (position, velocity) in query.iter() {
+                if let Some(velocity) = velocity {
+                    velocity.0 += position.0;
+                }
+            }
+            assert!(query.get(c).is_none());
+        }
+        assert_eq!(
+            world
+                .get_component::<Velocity>(a)
+                .map(|velocity| velocity.0),
+            Some(11)
+        );
+        assert_eq!(
+            world
+                .get_component::<Velocity>(c)
+                .map(|velocity| velocity.0),
+            Some(30)
+        );
+        let mut query = world.query::<Option<&Marker>>().unwrap();
+        assert_eq!(query.iter().filter(|marker| marker.is_none()).count(), 4);
+    }
+}
@@ -0,0 +1,941 @@
+use crate::adel_ecs::{
+    ErrorPolicy, Profiler, RunStage, State, StateData, System, SystemError, SystemErrors,
+    SystemPhase, SystemResult, World,
+};
+use std::any::{Any, TypeId};
+use std::cell::RefMut;
+use std::collections::HashMap;
+use std::fmt;
+use std::time::Instant;
+
+// Checked before every run, the system is skipped for the frame when any of them returns false
+pub type RunCondition = Box<dyn Fn(&World) -> bool>;
+
+// A system plus where it sits in its RunStage. The label defaults to System::name, before/after refer to the
+// labels of other systems in the same stage. Without an ErrorPolicy of its own the system falls back to the one
+// of the SystemErrors resource
+pub struct SystemDescriptor {
+    system: Box<dyn System>,
+    label: String,
+    before: Vec<String>,
+    after: Vec<String>,
+    policy: Option<ErrorPolicy>,
+    run_conditions: Vec<RunCondition>,
+}
+
+impl SystemDescriptor {
+    pub fn new(system: Box<dyn System>) -> Self {
+        let label = system.name().to_owned();
+        Self {
+            system,
+            label,
+            before: Vec::new(),
+            after: Vec::new(),
+            policy: None,
+            run_conditions: Vec::new(),
+        }
+    }
+}
+
+// Lets any System be handed to the Schedule directly or have ordering constraints chained on to it,
+// app.add_system(KeyboardHandler::new().before("WindowSystem"))
+pub trait IntoSystemDescriptor {
+    fn into_descriptor(self) -> SystemDescriptor;
+
+    fn label(self, label: &str) -> SystemDescriptor
+    where
+        Self: Sized,
+    {
+        let mut descriptor = self.into_descriptor();
+        descriptor.label = label.to_owned();
+        descriptor
+    }
+    fn before(self, label: &str) -> SystemDescriptor
+    where
+        Self: Sized,
+    {
+        let mut descriptor = self.into_descriptor();
+        descriptor.before.push(label.to_owned());
+        descriptor
+    }
+    fn after(self, label: &str) -> SystemDescriptor
+    where
+        Self: Sized,
+    {
+        let mut descriptor = self.into_descriptor();
+        descriptor.after.push(label.to_owned());
+        descriptor
+    }
+    fn on_error(self, policy: ErrorPolicy) -> SystemDescriptor
+    where
+        Self: Sized,
+    {
+        let mut descriptor = self.into_descriptor();
+        descriptor.policy = Some(policy);
+        descriptor
+    }
+    // Only affects run, startup and shutdown always happen
+    fn run_if(self, condition: impl Fn(&World) -> bool + 'static) -> SystemDescriptor
+    where
+        Self: Sized,
+    {
+        let mut descriptor = self.into_descriptor();
+        descriptor.run_conditions.push(Box::new(condition));
+        descriptor
+    }
+}
+
+impl IntoSystemDescriptor for SystemDescriptor {
+    fn into_descriptor(self) -> SystemDescriptor {
+        self
+    }
+}
+
+impl IntoSystemDescriptor for Box<dyn System> {
+    fn into_descriptor(self) -> SystemDescriptor {
+        SystemDescriptor::new(self)
+    }
+}
+
+impl<S: System + 'static> IntoSystemDescriptor for S {
+    fn into_descriptor(self) -> SystemDescriptor {
+        SystemDescriptor::new(Box::new(self))
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum ScheduleError {
+    DuplicateLabel(String),
+    // Labels of the systems on one of the cycles, each has to run before the next and the last before the first
+    Cycle(RunStage, Vec<String>),
+}
+
+impl fmt::Display for ScheduleError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            ScheduleError::DuplicateLabel(label) => {
+                write!(f, "more than one system is labelled {}", label)
+            }
+            ScheduleError::Cycle(stage, labels) => write!(
+                f,
+                "systems in {:?} have cyclic before/after constraints: {} -> {}",
+                stage,
+                labels.join(" -> "),
+                labels[0]
+            ),
+        }
+    }
+}
+
+impl std::error::Error for ScheduleError {}
+
+// The on_enter/on_exit systems of one value of a state
+#[derive(PartialEq)]
+enum StateSet {
+    OnEnter(StateKey),
+    OnExit(StateKey),
+}
+
+// A copy of the state value, type erased so the Schedule doesn't need to be generic over every state type.
+// Values are compared with the Eq of their own type
+struct StateKey {
+    value: Box<dyn Any>,
+    eq: fn(&dyn Any, &dyn Any) -> bool,
+}
+
+impl StateKey {
+    fn new<S: StateData>(state: &S) -> Self {
+        Self {
+            value: Box::new(state.clone()),
+            eq: state_eq::<S>,
+        }
+    }
+}
+
+impl PartialEq for StateKey {
+    fn eq(&self, other: &Self) -> bool {
+        (self.eq)(self.value.as_ref(), other.value.as_ref())
+    }
+}
+
+// Values of different state types are never equal
+fn state_eq<S: StateData>(value: &dyn Any, other: &dyn Any) -> bool {
+    match (value.downcast_ref::<S>(), other.downcast_ref::<S>()) {
+        (Some(value), Some(other)) => value == other,
+        _ => false,
+    }
+}
+
+// Applies the queued transition of one State<S> resource, see add_state
+#[derive(Copy, Clone)]
+struct StateDriver {
+    type_id: TypeId,
+    enter_initial: fn(&mut Schedule, &mut World),
+    apply_transition: fn(&mut Schedule, &mut World),
+}
+
+struct ScheduledSystem {
+    descriptor: SystemDescriptor,
+    stage: RunStage,
+    // Some for systems that only run on a state transition instead of every frame in their stage
+    state: Option<StateSet>,
+    // Tick the system last ran at, used by the World for change detection
+    last_run: u64,
+    // Set by ErrorPolicy::DisableSystem, a disabled system is skipped by startup and run_stage but still shut down
+    disabled: bool,
+    // Whether startup was called, only those are shut down
+    started: bool,
+}
+
+// Every system of the Application in the order they run. Within a RunStage systems are topologically sorted on
+// their before/after constraints, systems without constraints between them keep the order they were added in.
+// The order is computed by build, which the Application calls before startup and again at the start of any frame
+// after systems were added, starting the new ones up before they run.
+// on_enter/on_exit systems run in the order they were added, before/after constraints don't apply to them and their
+// labels only have to be unique among the systems of the same state value, so one system can be added to several.
+pub struct Schedule {
+    systems: Vec<ScheduledSystem>,
+    stage_order: HashMap<RunStage, Vec<usize>>,
+    states: Vec<StateDriver>,
+    is_built: bool,
+    // Set by run_startup, systems added later are started up by build_added_systems
+    has_started: bool,
+}
+
+impl Schedule {
+    pub fn new() -> Self {
+        Self {
+            systems: Vec::new(),
+            stage_order: HashMap::new(),
+            states: Vec::new(),
+            is_built: false,
+            has_started: false,
+        }
+    }
+    pub fn add_system(&mut self, system: impl IntoSystemDescriptor) {
+        self.push_system(system.into_descriptor(), None);
+    }
+    // Runs the system once every time State<S> changes to state
+    pub fn add_system_on_enter<S: StateData>(
+        &mut self,
+        state: S,
+        system: impl IntoSystemDescriptor,
+    ) {
+        self.push_system(
+            system.into_descriptor(),
+            Some(StateSet::OnEnter(StateKey::new(&state))),
+        );
+    }
+    // Runs the system once every time State<S> changes away from state
+    pub fn add_system_on_exit<S: StateData>(
+        &mut self,
+        state: S,
+        system: impl IntoSystemDescriptor,
+    ) {
+        self.push_system(
+            system.into_descriptor(),
+            Some(StateSet::OnExit(StateKey::new(&state))),
+        );
+    }
+    fn push_system(&mut self, descriptor: SystemDescriptor, state: Option<StateSet>) {
+        let stage = descriptor.system.get_run_stage();
+        self.systems.push(ScheduledSystem {
+            descriptor,
+            stage,
+            state,
+            last_run: 0,
+            disabled: false,
+            started: false,
+        });
+        self.is_built = false;
+    }
+    // Has the Schedule apply the transitions queued on the State<S> resource, the resource itself is inserted by
+    // whoever adds the state (AppBuilder::add_state)
+    pub fn add_state<S: StateData>(&mut self) {
+        if self
+            .states
+            .iter()
+            .any(|driver| driver.type_id == TypeId::of::<S>())
+        {
+            log::warn!(
+                "State {} was already added, skipping it",
+                std::any::type_name::<S>()
+            );
+            return;
+        }
+        self.states.push(StateDriver {
+            type_id: TypeId::of::<S>(),
+            enter_initial: enter_initial_state::<S>,
+            apply_transition: apply_state_transition::<S>,
+        });
+    }
+    pub fn build(&mut self) -> Result<(), ScheduleError> {
+        let mut labels: HashMap<&str, usize> = HashMap::new();
+        for (index, scheduled) in self.systems.iter().enumerate() {
+            let label = scheduled.descriptor.label.as_str();
+            let is_duplicate = match &scheduled.state {
+                None => labels.insert(label, index).is_some(),
+                // Nothing can be ordered against them, so they stay out of labels
+                Some(set) => self.systems[..index].iter().any(|other| {
+                    other.state.as_ref() == Some(set) && other.descriptor.label == label
+                }),
+            };
+            if is_duplicate {
+                return Err(ScheduleError::DuplicateLabel(label.to_owned()));
+            }
+        }
+        let mut stage_order = HashMap::new();
+        for stage in RunStage::ALL {
+            stage_order.insert(stage, self.sort_stage(stage, &labels)?);
+        }
+        self.stage_order = stage_order;
+        self.is_built = true;
+        Ok(())
+    }
+    // Builds the order again if systems were added since the last build and, once startup has run, starts the new
+    // systems up. If the systems don't make a valid schedule anymore the last order keeps being used, without the
+    // new systems
+    pub fn build_added_systems(&mut self, world: &mut World) -> Result<(), ScheduleError> {
+        if self.is_built {
+            return Ok(());
+        }
+        self.build()?;
+        if self.has_started {
+            self.start_systems(world);
+        }
+        Ok(())
+    }
+    pub fn is_built(&self) -> bool {
+        self.is_built
+    }
+    // Kahn's algorithm, always picking the earliest added system that is ready so the result is deterministic
+    fn sort_stage(
+        &self,
+        stage: RunStage,
+        labels: &HashMap<&str, usize>,
+    ) -> Result<Vec<usize>, ScheduleError> {
+        let members: Vec<usize> = (0..self.systems.len())
+            .filter(|index| self.runs_in_stage(*index, stage))
+            .collect();
+        // dependents[a] holds every system that has to run after a
+        let mut dependents: HashMap<usize, Vec<usize>> = HashMap::new();
+        let mut dependencies: HashMap<usize, usize> =
+            members.iter().map(|index| (*index, 0)).collect();
+        for index in members.iter().copied() {
+            let descriptor = &self.systems[index].descriptor;
+            let edges = descriptor
+                .before
+                .iter()
+                .map(|label| (label, true))
+                .chain(descriptor.after.iter().map(|label| (label, false)));
+            for (label, is_before) in edges {
+                let other = match labels.get(label.as_str()) {
+                    Some(other) => *other,
+                    None => {
+                        log::warn!(
+                            "System {} is ordered against unknown label {}",
+                            descriptor.label,
+                            label
+                        );
+                        continue;
+                    }
+                };
+                // Stages already run in a fixed order, constraints across stages have nothing to do
+                if !self.runs_in_stage(other, stage) {
+                    continue;
+                }
+                let (first, second) = if is_before {
+                    (index, other)
+                } else {
+                    (other, index)
+                };
+                dependents.entry(first).or_default().push(second);
+                *dependencies.get_mut(&second).unwrap() += 1;
+            }
+        }
+        let mut order = Vec::with_capacity(members.len());
+        while order.len() < members.len() {
+            let next = members
+                .iter()
+                .copied()
+                .find(|index| dependencies[index] == 0 && !order.contains(index));
+            let next = match next {
+                Some(next) => next,
+                None => {
+                    let cycle = find_cycle(&members, &order, &dependents)
+                        .into_iter()
+                        .map(|index| self.systems[index].descriptor.label.clone())
+                        .collect();
+                    return Err(ScheduleError::Cycle(stage, cycle));
+                }
+            };
+            order.push(next);
+            for dependent in dependents.get(&next).into_iter().flatten() {
+                *dependencies.get_mut(dependent).unwrap() -= 1;
+            }
+        }
+        Ok(order)
+    }
+    fn runs_in_stage(&self, index: usize, stage: RunStage) -> bool {
+        let scheduled = &self.systems[index];
+        scheduled.stage == stage && scheduled.state.is_none()
+    }
+    // Labels of a stage in the order they run, for debugging the schedule
+    pub fn stage_labels(&self, stage: RunStage) -> Vec<&str> {
+        self.stage_order
+            .get(&stage)
+            .into_iter()
+            .flatten()
+            .map(|index| self.systems[*index].descriptor.label.as_str())
+            .collect()
+    }
+    pub fn is_disabled(&self, label: &str) -> bool {
+        self.find(label).is_some_and(|scheduled| scheduled.disabled)
+    }
+    // Turns a system back on after an error disabled it
+    pub fn enable(&mut self, label: &str) {
+        self.set_disabled(label, false);
+    }
+    pub fn disable(&mut self, label: &str) {
+        self.set_disabled(label, true);
+    }
+    // Applies to every system with the label, on_enter/on_exit systems of different states can share one
+    fn set_disabled(&mut self, label: &str, disabled: bool) {
+        let mut found = false;
+        for scheduled in self
+            .systems
+            .iter_mut()
+            .filter(|scheduled| scheduled.descriptor.label == label)
+        {
+            scheduled.disabled = disabled;
+            found = true;
+        }
+        if !found {
+            log::warn!("No system is labelled {}", label);
+        }
+    }
+    fn find(&self, label: &str) -> Option<&ScheduledSystem> {
+        self.systems
+            .iter()
+            .find(|scheduled| scheduled.descriptor.label == label)
+    }
+    // Only systems that were part of the last build
+    fn ordered(&self) -> Vec<usize> {
+        RunStage::ALL
+            .iter()
+            .flat_map(|stage| self.stage_order.get(stage).into_iter().flatten().copied())
+            .chain((0..self.systems.len()).filter(|index| self.systems[*index].state.is_some()))
+            .collect()
+    }
+    pub fn run_startup(&mut self, world: &mut World) {
+        let start = Instant::now();
+        self.start_systems(world);
+        self.has_started = true;
+        // Every state starts out entered
+        for driver in self.states.clone() {
+            (driver.enter_initial)(self, world);
+        }
+        if let Some(mut profiler) = profiler(world) {
+            profiler.record_span("Startup", "startup", start, start.elapsed());
+            profiler.start_first_frame();
+        }
+    }
+    // Starts up every system that hasn't been yet
+    fn start_systems(&mut self, world: &mut World) {
+        for index in self.ordered() {
+            if !self.systems[index].started {
+                self.systems[index].started = true;
+                self.run_system(index, SystemCall::Startup, world);
+            }
+        }
+        world.apply_commands();
+    }
+    // Called by the Application at the start of every frame, before EarlyUpdate. Runs the on_exit systems of the
+    // old state and the on_enter systems of the new one for every State with a transition queued
+    pub fn apply_state_transitions(&mut self, world: &mut World) {
+        for driver in self.states.clone() {
+            (driver.apply_transition)(self, world);
+        }
+    }
+    fn run_state_set(&mut self, set: StateSet, world: &mut World) {
+        let start = Instant::now();
+        let members: Vec<usize> = (0..self.systems.len())
+            .filter(|index| self.systems[*index].state.as_ref() == Some(&set))
+            .collect();
+        for index in members {
+            self.run_system(index, SystemCall::Run, world);
+        }
+        world.apply_commands();
+        if let Some(mut profiler) = profiler(world) {
+            let name = match set {
+                StateSet::OnEnter(_) => "OnEnter",
+                StateSet::OnExit(_) => "OnExit",
+            };
+            profiler.record_span(name, "state", start, start.elapsed());
+        }
+    }
+    // Runs every system of the stage, then applies the commands they queued
+    pub fn run_stage(&mut self, stage: RunStage, world: &mut World) {
+        // The Application builds at the start of the frame and reports the error, this only covers running the
+        // Schedule on its own
+        if let Err(error) = self.build_added_systems(world) {
+            log::error!(
+                "Systems added to {:?} don't make a valid schedule, running without them: {}",
+                stage,
+                error
+            );
+        }
+        let start = Instant::now();
+        let order = self.stage_order.get(&stage).cloned().unwrap_or_default();
+        for index in order {
+            self.run_system(index, SystemCall::Run, world);
+        }
+        // Sync point, nothing is borrowed between stages
+        world.apply_commands();
+        if let Some(mut profiler) = profiler(world) {
+            profiler.record_stage(stage, start, start.elapsed());
+        }
+    }
+    pub fn run_shutdown(&mut self, world: &mut World) {
+        let start = Instant::now();
+        for index in self.ordered() {
+            if self.systems[index].started {
+                self.run_system(index, SystemCall::Shutdown, world);
+            }
+        }
+        world.apply_commands();
+        if let Some(mut profiler) = profiler(world) {
+            profiler.record_span("Shutdown", "shutdown", start, start.elapsed());
+        }
+    }
+    fn run_system(&mut self, index: usize, call: SystemCall, world: &mut World) {
+        let ScheduledSystem {
+            descriptor,
+            stage,
+            state,
+            last_run,
+            disabled,
+            ..
+        } = &mut self.systems[index];
+        if *disabled && !matches!(call, SystemCall::Shutdown) {
+            return;
+        }
+        if matches!(call, SystemCall::Run)
+            && !descriptor
+                .run_conditions
+                .iter()
+                .all(|condition| condition(world))
+        {
+            return;
+        }
+        let start = Instant::now();
+        let result: SystemResult = run_with_ticks(world, last_run, |world| match call {
+            SystemCall::Startup => descriptor.system.startup(world),
+            SystemCall::Run => descriptor.system.run(world),
+            SystemCall::Shutdown => descriptor.system.shutdown(world),
+        });
+        let duration = start.elapsed();
+        if let Err(error) = result {
+            let phase = match (call, state.as_ref()) {
+                (SystemCall::Startup, _) => SystemPhase::Startup,
+                (SystemCall::Run, Some(StateSet::OnEnter(_))) => SystemPhase::OnEnter,
+                (SystemCall::Run, Some(StateSet::OnExit(_))) => SystemPhase::OnExit,
+                (SystemCall::Run, None) => SystemPhase::Run(*stage),
+                (SystemCall::Shutdown, _) => SystemPhase::Shutdown,
+            };
+            if handle_error(world, descriptor, phase, error) {
+                *disabled = true;
+            }
+        }
+        if let Some(mut profiler) = profiler(world) {
+            match call {
+                SystemCall::Startup => {
+                    profiler.record_span(&descriptor.label, "startup", start, duration)
+                }
+                SystemCall::Run if state.is_some() => {
+                    profiler.record_span(&descriptor.label, "state", start, duration)
+                }
+                SystemCall::Run => {
+                    profiler.record_system(*stage, &descriptor.label, start, duration)
+                }
+                SystemCall::Shutdown => {
+                    profiler.record_span(&descriptor.label, "shutdown", start, duration)
+                }
+            }
+        }
+    }
+}
+
+// Called once the sort is stuck. Every system left over still waits on another left over system, so walking
+// from one of them to a system it waits on has to come back around to a system already visited
+fn find_cycle(
+    members: &[usize],
+    order: &[usize],
+    dependents: &HashMap<usize, Vec<usize>>,
+) -> Vec<usize> {
+    let waits_on = |index: usize| {
+        members.iter().copied().find(|other| {
+            !order.contains(other)
+                && dependents
+                    .get(other)
+                    .is_some_and(|dependents| dependents.contains(&index))
+        })
+    };
+    let mut path: Vec<usize> = Vec::new();
+    let mut current = members.iter().copied().find(|index| !order.contains(index));
+    while let Some(index) = current {
+        if let Some(start) = path.iter().position(|visited| *visited == index) {
+            // The walk went against the edges, flip it back into run order starting at the earliest added system
+            let mut cycle = path.split_off(start);
+            cycle.reverse();
+            let earliest = (0..cycle.len())
+                .min_by_key(|position| cycle[*position])
+                .unwrap();
+            cycle.rotate_left(earliest);
+            return cycle;
+        }
+        path.push(index);
+        current = waits_on(index);
+    }
+    path
+}
+
+#[derive(Copy, Clone)]
+enum SystemCall {
+    Startup,
+    Run,
+    Shutdown,
+}
+
+fn enter_initial_state<S: StateData>(schedule: &mut Schedule, world: &mut World) {
+    if !world.contains_resource::<State<S>>() {
+        log::warn!(
+            "State {} was added without a State resource",
+            std::any::type_name::<S>()
+        );
+        return;
+    }
+    let key = StateKey::new(world.get_resource::<State<S>>().unwrap().current());
+    schedule.run_state_set(StateSet::OnEnter(key), world);
+}
+
+fn apply_state_transition<S: StateData>(schedule: &mut Schedule, world: &mut World) {
+    if !world.contains_resource::<State<S>>() {
+        return;
+    }
+    let transition = world
+        .get_resource_mut::<State<S>>()
+        .unwrap()
+        .take_transition();
+    if let Some((from, to)) = transition {
+        log::info!("State transition {:?} -> {:?}", from, to);
+        schedule.run_state_set(StateSet::OnExit(StateKey::new(&from)), world);
+        schedule.run_state_set(StateSet::OnEnter(StateKey::new(&to)), world);
+    }
+}
+
+// Logs and records the error, then applies the system's policy. Returns true when the system should be disabled.
+// Errors during shutdown are only logged and recorded, every other system still gets shut down
+fn handle_error(
+    world: &World,
+    descriptor: &SystemDescriptor,
+    phase: SystemPhase,
+    error: anyhow::Error,
+) -> bool {
+    let error = SystemError {
+        label: descriptor.label.clone(),
+        phase,
+        error,
+    };
+    log::warn!("{}", error);
+    let mut errors = system_errors(world);
+    let policy = descriptor
+        .policy
+        .or(errors.as_ref().map(|errors| errors.policy()))
+        .unwrap_or_default();
+    if let Some(errors) = errors.as_mut() {
+        errors.push(error);
+    }
+    if phase == SystemPhase::Shutdown {
+        return false;
+    }
+    match policy {
+        ErrorPolicy::LogAndContinue => false,
+        ErrorPolicy::DisableSystem => {
+            log::warn!("Disabling system {}", descriptor.label);
+            true
+        }
+        ErrorPolicy::Shutdown => {
+            match errors.as_mut() {
+                Some(errors) => {
+                    log::warn!("System {} requested a shutdown", descriptor.label);
+                    errors.request_shutdown();
+                }
+                None => log::warn!(
+                    "System {} requested a shutdown but there is no SystemErrors resource",
+                    descriptor.label
+                ),
+            }
+            false
+        }
+    }
+}
+
+fn system_errors(world: &World) -> Option<RefMut<'_, SystemErrors>> {
+    if !world.contains_resource::<SystemErrors>() {
+        return None;
+    }
+    world.get_resource_mut::<SystemErrors>()
+}
+
+// Profiling is only on while there is a Profiler resource
+fn profiler(world: &World) -> Option<RefMut<'_, Profiler>> {
+    if !world.contains_resource::<Profiler>() {
+        return None;
+    }
+    world.get_resource_mut::<Profiler>()
+}
+
+impl Default for Schedule {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+// Runs one system call so that it only sees the component changes made since the last time it ran
+fn run_with_ticks<R>(
+    world: &mut World,
+    last_run: &mut u64,
+    run: impl FnOnce(&mut World) -> R,
+) -> R {
+    world.set_last_change_tick(*last_run);
+    let result = run(world);
+    *last_run = world.increment_change_tick();
+    result
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    struct TestSystem {
+        name: &'static str,
+        stage: RunStage,
+    }
+
+    impl System for TestSystem {
+        fn startup(&mut self, _world: &mut World) -> SystemResult {
+            Ok(())
+        }
+        // Leaves its name in the RunLog, if there is one
+        fn run(&mut self, world: &mut World) -> SystemResult {
+            if world.contains_resource::<RunLog>() {
+                world
+                    .get_resource_mut::<RunLog>()
+                    .unwrap()
+                    .0
+                    .push(self.name);
+            }
+            Ok(())
+        }
+        fn shutdown(&mut self, _world: &mut World) -> SystemResult {
+            Ok(())
+        }
+        fn name(&self) -> &str {
+            self.name
+        }
+        fn get_run_stage(&self) -> RunStage {
+            self.stage
+        }
+    }
+
+    #[derive(Default)]
+    struct RunLog(Vec<&'static str>);
+
+    fn system(name: &'static str) -> TestSystem {
+        TestSystem {
+            name,
+            stage: RunStage::Update,
+        }
+    }
+
+    #[test]
+    fn unconstrained_systems_keep_the_order_they_were_added_in() {
+        let mut schedule = Schedule::new();
+        schedule.add_system(system("c"));
+        schedule.add_system(system("a"));
+        schedule.add_system(system("b"));
+        schedule.build().unwrap();
+        assert_eq!(schedule.stage_labels(RunStage::Update), vec!["c", "a", "b"]);
+    }
+
+    #[test]
+    fn before_and_after_are_sorted_topologically() {
+        let mut schedule = Schedule::new();
+        schedule.add_system(system("c"));
+        schedule.add_system(system("a").before("c"));
+        schedule.add_system(system("b").after("a").before("c"));
+        schedule.add_system(system("d"));
+        schedule.build().unwrap();
+        assert_eq!(
+            schedule.stage_labels(RunStage::Update),
+            vec!["a", "b", "c", "d"]
+        );
+    }
+
+    #[test]
+    fn constraints_across_stages_are_ignored() {
+        let mut schedule = Schedule::new();
+        schedule.add_system(system("update").before("early"));
+        schedule.add_system(TestSystem {
+            name: "early",
+            stage: RunStage::EarlyUpdate,
+        });
+        schedule.build().unwrap();
+        assert_eq!(schedule.stage_labels(RunStage::EarlyUpdate), vec!["early"]);
+        assert_eq!(schedule.stage_labels(RunStage::Update), vec!["update"]);
+    }
+
+    #[test]
+    fn duplicate_labels_are_rejected() {
+        let mut schedule = Schedule::new();
+        schedule.add_system(system("a"));
+        schedule.add_system(system("b").label("a"));
+        assert_eq!(
+            schedule.build(),
+            Err(ScheduleError::DuplicateLabel("a".to_owned()))
+        );
+    }
+
+    #[test]
+    fn cycle_reports_only_the_systems_on_it() {
+        let mut schedule = Schedule::new();
+        schedule.add_system(system("first").before("a"));
+        schedule.add_system(system("a").before("b"));
+        schedule.add_system(system("b").before("c"));
+        schedule.add_system(system("c").before("a"));
+        // Stuck behind the cycle without being part of it
+        schedule.add_system(system("last").after("c"));
+        assert_eq!(
+            schedule.build(),
+            Err(ScheduleError::Cycle(
+                RunStage::Update,
+                vec!["a".to_owned(), "b".to_owned(), "c".to_owned()]
+            ))
+        );
+    }
+
+    #[test]
+    fn system_ordered_against_itself_is_a_cycle() {
+        let mut schedule = Schedule::new();
+        schedule.add_system(system("a"));
+        schedule.add_system(system("b").before("b"));
+        assert_eq!(
+            schedule.build(),
+            Err(ScheduleError::Cycle(RunStage::Update, vec!["b".to_owned()]))
+        );
+    }
+
+    #[test]
+    fn run_stage_builds_systems_added_after_startup() {
+        let mut world = World::new();
+        world.insert_resource(RunLog::default());
+        let mut schedule = Schedule::new();
+        schedule.add_system(system("a"));
+        schedule.build().unwrap();
+        schedule.run_startup(&mut world);
+        schedule.add_system(system("b").before("a"));
+        schedule.run_stage(RunStage::Update, &mut world);
+        assert_eq!(world.get_resource::<RunLog>().unwrap().0, vec!["b", "a"]);
+        // A system that doesn't fit is left out instead of stopping the others
+        schedule.add_system(system("c").before("c"));
+        schedule.run_stage(RunStage::Update, &mut world);
+        assert_eq!(
+            world.get_resource::<RunLog>().unwrap().0,
+            vec!["b", "a", "b", "a"]
+        );
+    }
+
+    #[derive(Debug, Clone, PartialEq, Eq, Hash)]
+    enum Mode {
+        Menu,
+        Playing,
+        Paused,
+    }
+
+    fn state_world<S: StateData>(initial: S) -> World {
+        let mut world = World::new();
+        world.insert_resource(State::new(initial));
+        world.insert_resource(RunLog::default());
+        world
+    }
+
+    fn run_log(world: &World) -> Vec<&'static str> {
+        world.get_resource::<RunLog>().unwrap().0.clone()
+    }
+
+    #[test]
+    fn one_system_can_be_added_to_several_states() {
+        let mut schedule = Schedule::new();
+        schedule.add_system(system("enter"));
+        schedule.add_system_on_enter(Mode::Menu, system("enter"));
+        schedule.add_system_on_enter(Mode::Playing, system("enter"));
+        schedule.add_system_on_exit(Mode::Menu, system("enter"));
+        schedule.add_state::<Mode>();
+        schedule.build().unwrap();
+
+        let mut world = state_world(Mode::Menu);
+        schedule.run_startup(&mut world);
+        assert_eq!(run_log(&world), vec!["enter"]);
+        world
+            .get_resource_mut::<State<Mode>>()
+            .unwrap()
+            .set(Mode::Playing);
+        schedule.apply_state_transitions(&mut world);
+        assert_eq!(run_log(&world), vec!["enter", "enter", "enter"]);
+        world
+            .get_resource_mut::<State<Mode>>()
+            .unwrap()
+            .set(Mode::Paused);
+        schedule.apply_state_transitions(&mut world);
+        assert_eq!(run_log(&world), vec!["enter", "enter", "enter"]);
+    }
+
+    #[test]
+    fn duplicate_labels_within_a_state_are_rejected() {
+        let mut schedule = Schedule::new();
+        schedule.add_system_on_enter(Mode::Menu, system("enter"));
+        schedule.add_system_on_enter(Mode::Menu, system("enter"));
+        assert_eq!(
+            schedule.build(),
+            Err(ScheduleError::DuplicateLabel("enter".to_owned()))
+        );
+    }
+
+    // Every value hashes the same
+    #[derive(Debug, Clone, PartialEq, Eq)]
+    struct Level(u32);
+
+    impl std::hash::Hash for Level {
+        fn hash<H: std::hash::Hasher>(&self, _state: &mut H) {}
+    }
+
+    #[test]
+    fn state_values_are_compared_not_hashed() {
+        let mut schedule = Schedule::new();
+        schedule.add_system_on_enter(Level(1), system("first"));
+        schedule.add_system_on_enter(Level(2), system("second"));
+        schedule.add_state::<Level>();
+        schedule.build().unwrap();
+
+        let mut world = state_world(Level(1));
+        schedule.run_startup(&mut world);
+        assert_eq!(run_log(&world), vec!["first"]);
+        world
+            .get_resource_mut::<State<Level>>()
+            .unwrap()
+            .set(Level(2));
+        schedule.apply_state_transitions(&mut world);
+        assert_eq!(run_log(&world), vec!["first", "second"]);
+    }
+}
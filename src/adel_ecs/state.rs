@@ -0,0 +1,59 @@
+use crate::adel_ecs::World;
+use std::fmt::Debug;
+use std::hash::Hash;
+
+// Anything usable as an application state, usually a plain enum like Menu/Loading/Playing/Paused
+pub trait StateData: Clone + Eq + Hash + Debug + 'static {}
+
+impl<S: Clone + Eq + Hash + Debug + 'static> StateData for S {}
+
+// Resource holding the current state of type S. Transitions are queued with set and applied by the Schedule at
+// the start of the next frame, running the on_exit systems of the old state and then the on_enter systems of the
+// new one. Only one transition happens per frame, the last state set during a frame wins.
+pub struct State<S: StateData> {
+    current: S,
+    previous: Option<S>,
+    queued: Option<S>,
+}
+
+impl<S: StateData> State<S> {
+    pub fn new(initial: S) -> Self {
+        Self {
+            current: initial,
+            previous: None,
+            queued: None,
+        }
+    }
+    pub fn current(&self) -> &S {
+        &self.current
+    }
+    // The state before the last transition, None until the first transition
+    pub fn previous(&self) -> Option<&S> {
+        self.previous.as_ref()
+    }
+    pub fn queued(&self) -> Option<&S> {
+        self.queued.as_ref()
+    }
+    pub fn set(&mut self, next: S) {
+        self.queued = Some(next);
+    }
+    // Setting the state it's already in does nothing, on_exit/on_enter don't run again
+    pub(crate) fn take_transition(&mut self) -> Option<(S, S)> {
+        let next = self.queued.take()?;
+        if next == self.current {
+            return None;
+        }
+        let previous = std::mem::replace(&mut self.current, next.clone());
+        self.previous = Some(previous.clone());
+        Some((previous, next))
+    }
+}
+
+// Run condition for systems that should only run in one state,
+// app.add_system(PlayerMovement::new().run_if(in_state(GameState::Playing)))
+pub fn in_state<S: StateData>(state: S) -> impl Fn(&World) -> bool {
+    move |world| {
+        world.contains_resource::<State<S>>()
+            && *world.get_resource::<State<S>>().unwrap().current() == state
+    }
+}
@@ -0,0 +1,229 @@
+use crate::adel_ecs::Entity;
+
+// The World change tick a component was added at and last written at
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct ComponentTicks {
+    pub added: u64,
+    pub changed: u64,
+}
+
+impl ComponentTicks {
+    // Ticks are compared against the tick a system last ran at, anything newer happened since then
+    pub fn is_added(&self, last_run: u64) -> bool {
+        self.added > last_run
+    }
+    pub fn is_changed(&self, last_run: u64) -> bool {
+        self.changed > last_run
+    }
+}
+
+// Sparse set storage for every instance of a single component type. The components themselves are packed
+// together in dense so iterating a component touches only the entities that have it, while sparse maps an
+// entity index to its position in dense for constant time lookups. The owning Entity (index + generation)
+// is kept next to each component so a lookup with a stale handle misses rather than handing back the
+// component of the entity that recycled the index.
+pub struct ComponentStorage<T> {
+    sparse: Vec<Option<usize>>,
+    dense: Vec<T>,
+    entities: Vec<Entity>,
+    ticks: Vec<ComponentTicks>,
+    // Entities that lost this component and the tick it happened at, cleared by World::clear_trackers
+    removed: Vec<(Entity, u64)>,
+    // Tick stamped on every mutable access, the World updates it whenever the storage is borrowed mutably
+    change_tick: u64,
+}
+
+impl<T> ComponentStorage<T> {
+    pub(crate) fn new() -> Self {
+        Self {
+            sparse: Vec::new(),
+            dense: Vec::new(),
+            entities: Vec::new(),
+            ticks: Vec::new(),
+            removed: Vec::new(),
+            change_tick: 0,
+        }
+    }
+    pub(crate) fn set_change_tick(&mut self, change_tick: u64) {
+        self.change_tick = change_tick;
+    }
+    fn dense_index(&self, entity: Entity) -> Option<usize> {
+        match self.sparse.get(entity.index()) {
+            Some(Some(dense_index)) if self.entities[*dense_index] == entity => Some(*dense_index),
+            _ => None,
+        }
+    }
+    // Returns the previous component if the entity already had one
+    pub(crate) fn insert(&mut self, entity: Entity, component: T) -> Option<T> {
+        if entity.index() >= self.sparse.len() {
+            self.sparse.resize(entity.index() + 1, None);
+        }
+        if let Some(dense_index) = self.sparse[entity.index()] {
+            let previous_owner = std::mem::replace(&mut self.entities[dense_index], entity);
+            let old = std::mem::replace(&mut self.dense[dense_index], component);
+            // A different generation means the old component was left behind by a despawned entity
+            if previous_owner == entity {
+                self.ticks[dense_index].changed = self.change_tick;
+                return Some(old);
+            }
+            self.ticks[dense_index] = ComponentTicks {
+                added: self.change_tick,
+                changed: self.change_tick,
+            };
+            return None;
+        }
+        self.sparse[entity.index()] = Some(self.dense.len());
+        self.dense.push(component);
+        self.entities.push(entity);
+        self.ticks.push(ComponentTicks {
+            added: self.change_tick,
+            changed: self.change_tick,
+        });
+        None
+    }
+    pub(crate) fn remove(&mut self, entity: Entity) -> Option<T> {
+        let dense_index = self.dense_index(entity)?;
+        self.sparse[entity.index()] = None;
+        // Swap remove keeps dense packed, the entity that was moved into the hole needs its sparse entry fixed
+        self.entities.swap_remove(dense_index);
+        self.ticks.swap_remove(dense_index);
+        let component = self.dense.swap_remove(dense_index);
+        if let Some(moved) = self.entities.get(dense_index) {
+            self.sparse[moved.index()] = Some(dense_index);
+        }
+        self.removed.push((entity, self.change_tick));
+        Some(component)
+    }
+    pub(crate) fn clear_removed_before(&mut self, tick: u64) {
+        self.removed
+            .retain(|(_, removed_tick)| *removed_tick >= tick);
+    }
+    pub fn contains(&self, entity: Entity) -> bool {
+        self.dense_index(entity).is_some()
+    }
+    pub fn get(&self, entity: Entity) -> Option<&T> {
+        self.dense_index(entity)
+            .map(|dense_index| &self.dense[dense_index])
+    }
+    // Handing out a mutable reference counts as a change, whether or not it ends up being written to
+    pub fn get_mut(&mut self, entity: Entity) -> Option<&mut T> {
+        let dense_index = self.dense_index(entity)?;
+        self.ticks[dense_index].changed = self.change_tick;
+        Some(&mut self.dense[dense_index])
+    }
+    pub fn ticks(&self, entity: Entity) -> Option<ComponentTicks> {
+        self.dense_index(entity)
+            .map(|dense_index| self.ticks[dense_index])
+    }
+    pub fn is_added(&self, entity: Entity, last_run: u64) -> bool {
+        self.ticks(entity)
+            .map_or(false, |ticks| ticks.is_added(last_run))
+    }
+    pub fn is_changed(&self, entity: Entity, last_run: u64) -> bool {
+        self.ticks(entity)
+            .map_or(false, |ticks| ticks.is_changed(last_run))
+    }
+    // Entities that lost this component after last_run, either through removal or despawning
+    pub fn removed_since(&self, last_run: u64) -> impl Iterator<Item = Entity> + '_ {
+        self.removed
+            .iter()
+            .filter(move |(_, tick)| *tick > last_run)
+            .map(|(entity, _)| *entity)
+    }
+    // Every entity that has this component, in the same order as iter()
+    pub fn entities(&self) -> &[Entity] {
+        &self.entities
+    }
+    // Iterates only the entities that actually have this component
+    pub fn iter(&self) -> impl Iterator<Item = (Entity, &T)> {
+        self.entities.iter().copied().zip(self.dense.iter())
+    }
+    // Marks every component as changed
+    pub fn iter_mut(&mut self) -> impl Iterator<Item = (Entity, &mut T)> {
+        let change_tick = self.change_tick;
+        for ticks in self.ticks.iter_mut() {
+            ticks.changed = change_tick;
+        }
+        self.entities.iter().copied().zip(self.dense.iter_mut())
+    }
+    // Raw access for queries handing out &mut T of several entities at once. sparse and entities are only
+    // read, the components are reached through the dense buffer pointer so references already handed out for
+    // other entities are never reborrowed.
+    // Safety: this must point to a live storage that is exclusively borrowed by the caller
+    pub(crate) unsafe fn contains_raw(this: *const Self, entity: Entity) -> bool {
+        (*this).contains(entity)
+    }
+    // Safety: same as contains_raw, and the returned pointer must not be turned into two live references
+    pub(crate) unsafe fn get_raw_mut(this: *mut Self, entity: Entity) -> Option<*mut T> {
+        let dense_index = (*this).dense_index(entity)?;
+        let change_tick = (*this).change_tick;
+        let ticks = std::ptr::addr_of_mut!((*this).ticks);
+        (*(*ticks).as_mut_ptr().add(dense_index)).changed = change_tick;
+        let dense = std::ptr::addr_of_mut!((*this).dense);
+        Some((*dense).as_mut_ptr().add(dense_index))
+    }
+    // Safety: same as contains_raw, the slice must not outlive the exclusive borrow
+    pub(crate) unsafe fn entities_raw<'a>(this: *const Self) -> &'a [Entity] {
+        &(*this).entities
+    }
+    pub fn len(&self) -> usize {
+        self.dense.len()
+    }
+    pub fn is_empty(&self) -> bool {
+        self.dense.is_empty()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn lookups_check_the_generation() {
+        let mut storage = ComponentStorage::new();
+        let old = Entity::new(0, 0);
+        let recycled = Entity::new(0, 1);
+        assert_eq!(storage.insert(old, "old"), None);
+        assert_eq!(storage.get(recycled), None);
+        assert!(!storage.contains(recycled));
+        assert_eq!(storage.remove(recycled), None);
+        assert_eq!(storage.len(), 1);
+        // A component left behind under an older generation is replaced, not handed back as the previous value
+        assert_eq!(storage.insert(recycled, "recycled"), None);
+        assert_eq!(storage.get(old), None);
+        assert_eq!(storage.get(recycled), Some(&"recycled"));
+        assert_eq!(storage.insert(recycled, "again"), Some("recycled"));
+        assert_eq!(storage.len(), 1);
+    }
+
+    #[test]
+    fn swap_remove_keeps_lookups_valid() {
+        let mut storage = ComponentStorage::new();
+        let entities: Vec<Entity> = (0..4).map(|index| Entity::new(index, 0)).collect();
+        for (value, entity) in entities.iter().enumerate() {
+            storage.insert(*entity, value);
+        }
+        assert_eq!(storage.remove(entities[1]), Some(1));
+        assert_eq!(storage.remove(entities[1]), None);
+        assert_eq!(storage.entities(), &[entities[0], entities[3], entities[2]]);
+        for (value, entity) in entities.iter().enumerate() {
+            let expected = if value == 1 { None } else { Some(&value) };
+            assert_eq!(storage.get(*entity), expected);
+        }
+        // Removing the last element has nothing to move
+        assert_eq!(storage.remove(entities[2]), Some(2));
+        assert_eq!(
+            storage.iter().collect::<Vec<_>>(),
+            vec![(entities[0], &0), (entities[3], &3)]
+        );
+        // Raw access finds the moved component where sparse now points
+        let pointer = &mut storage as *mut ComponentStorage<usize>;
+        unsafe {
+            assert!(ComponentStorage::contains_raw(pointer, entities[3]));
+            assert!(!ComponentStorage::contains_raw(pointer, entities[1]));
+            *ComponentStorage::get_raw_mut(pointer, entities[3]).unwrap() = 30;
+            assert!(ComponentStorage::get_raw_mut(pointer, entities[2]).is_none());
+        }
+        assert_eq!(storage.get(entities[3]), Some(&30));
+    }
+}
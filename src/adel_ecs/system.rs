@@ -1,20 +1,151 @@
 use crate::adel_ecs::world::World;
+use std::collections::VecDeque;
+use std::fmt;
 
-#[derive(PartialEq)]
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
 pub enum RunStage {
     EarlyUpdate,
+    // Runs zero or more times per frame at the rate of the FixedTimestep resource
+    FixedUpdate,
     Update,
     RedrawUpdate,
     LateUpdate,
 }
 
+impl RunStage {
+    // Every stage in the order they run within a frame
+    pub const ALL: [RunStage; 5] = [
+        RunStage::EarlyUpdate,
+        RunStage::FixedUpdate,
+        RunStage::Update,
+        RunStage::RedrawUpdate,
+        RunStage::LateUpdate,
+    ];
+}
+
+// Systems hand errors back to the Schedule instead of panicking, what happens next is up to their ErrorPolicy
+pub type SystemResult = anyhow::Result<()>;
+
 pub trait System {
-    fn startup(&mut self, world: &mut World);
-    fn run(&mut self, world: &mut World);
-    fn shutdown(&mut self, world: &mut World);
+    fn startup(&mut self, world: &mut World) -> SystemResult;
+    fn run(&mut self, world: &mut World) -> SystemResult;
+    fn shutdown(&mut self, world: &mut World) -> SystemResult;
     fn name(&self) -> &str;
 
     fn get_run_stage(&self) -> RunStage {
         RunStage::Update
     }
 }
+
+// What the Schedule does when a system returns an error. Every error is logged and recorded in the
+// SystemErrors resource whatever the policy is
+#[derive(Debug, Default, Copy, Clone, PartialEq, Eq)]
+pub enum ErrorPolicy {
+    // Keep running the system every frame
+    #[default]
+    LogAndContinue,
+    // Stop running the system, its shutdown still runs when the Application shuts down
+    DisableSystem,
+    // Request a shutdown through the SystemErrors resource, the Application stops at the end of the frame, shuts
+    // every system down cleanly and exits with ExitStatus::Failure
+    Shutdown,
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum SystemPhase {
+    Startup,
+    Run(RunStage),
+    // Running as an on_enter/on_exit system of a state transition
+    OnEnter,
+    OnExit,
+    Shutdown,
+}
+
+#[derive(Debug)]
+pub struct SystemError {
+    pub label: String,
+    pub phase: SystemPhase,
+    pub error: anyhow::Error,
+}
+
+impl fmt::Display for SystemError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(
+            f,
+            "system {} failed in {:?}: {:#}",
+            self.label, self.phase, self.error
+        )
+    }
+}
+
+// Resource the Schedule records system errors into. Only the most recent errors are kept so a system failing
+// every frame doesn't grow it forever
+pub struct SystemErrors {
+    errors: VecDeque<SystemError>,
+    capacity: usize,
+    policy: ErrorPolicy,
+    shutdown_requested: bool,
+}
+
+impl SystemErrors {
+    pub const DEFAULT_CAPACITY: usize = 64;
+
+    pub fn new() -> Self {
+        Self {
+            errors: VecDeque::new(),
+            capacity: Self::DEFAULT_CAPACITY,
+            policy: ErrorPolicy::default(),
+            shutdown_requested: false,
+        }
+    }
+    pub fn with_capacity(mut self, capacity: usize) -> Self {
+        self.capacity = capacity.max(1);
+        self
+    }
+    // Policy for systems that weren't given one with on_error
+    pub fn with_policy(mut self, policy: ErrorPolicy) -> Self {
+        self.policy = policy;
+        self
+    }
+    pub fn policy(&self) -> ErrorPolicy {
+        self.policy
+    }
+    pub fn set_policy(&mut self, policy: ErrorPolicy) {
+        self.policy = policy;
+    }
+    // Set when a system with ErrorPolicy::Shutdown failed, the Application checks it at the end of every frame
+    pub fn shutdown_requested(&self) -> bool {
+        self.shutdown_requested
+    }
+    pub(crate) fn request_shutdown(&mut self) {
+        self.shutdown_requested = true;
+    }
+    pub fn push(&mut self, error: SystemError) {
+        if self.errors.len() == self.capacity {
+            self.errors.pop_front();
+        }
+        self.errors.push_back(error);
+    }
+    // Oldest first
+    pub fn iter(&self) -> impl Iterator<Item = &SystemError> {
+        self.errors.iter()
+    }
+    pub fn last(&self) -> Option<&SystemError> {
+        self.errors.back()
+    }
+    pub fn len(&self) -> usize {
+        self.errors.len()
+    }
+    pub fn is_empty(&self) -> bool {
+        self.errors.is_empty()
+    }
+    pub fn clear(&mut self) {
+        self.errors.clear();
+    }
+}
+
+impl Default for SystemErrors {
+    fn default() -> Self {
+        Self::new()
+    }
+}
@@ -1,3 +1,10 @@
+use crate::adel_ecs::commands::Command;
+use crate::adel_ecs::entity::Entities;
+use crate::adel_ecs::event::update_events;
+use crate::adel_ecs::{
+    Commands, ComponentStorage, Entity, EventWriter, Events, Query, QueryData, QueryError,
+    QueryFilter,
+};
 use crate::adel_tools::print_type_of;
 use std::any::TypeId;
 use std::cell::{Ref, RefCell, RefMut};
@@ -8,13 +15,15 @@ use std::collections::HashMap;
 pub trait Component {
     fn component_as_any(&self) -> &dyn std::any::Any;
     fn component_as_any_mut(&mut self) -> &mut dyn std::any::Any;
-    fn push_none(&mut self);
+    fn remove_entity(&mut self, entity: Entity, change_tick: u64);
+    fn clear_removed_before(&mut self, tick: u64);
+    fn contains_entity(&self, entity: Entity) -> Result<bool, QueryError>;
+    fn component_type_name(&self) -> &'static str;
 }
 
 // Every component type needs to be known at run time and last the duration of the program
-// Implemented for a mutable vectors that could contain the component
-// All component vectors have the same size
-impl<T: 'static> Component for RefCell<Vec<Option<T>>> {
+// Implemented for a mutable storage that could contain the component
+impl<T: 'static> Component for RefCell<ComponentStorage<T>> {
     // Borrow Vec of EntityIds that represent a component
     fn component_as_any(&self) -> &dyn std::any::Any {
         self as &dyn std::any::Any
@@ -23,9 +32,24 @@ impl<T: 'static> Component for RefCell<Vec<Option<T>>> {
     fn component_as_any_mut(&mut self) -> &mut dyn std::any::Any {
         self as &mut dyn std::any::Any
     }
-    // Push empty into that vector location
-    fn push_none(&mut self) {
-        self.get_mut().push(None)
+    // Clear the slot of a despawned entity, dropping the component
+    fn remove_entity(&mut self, entity: Entity, change_tick: u64) {
+        let storage = self.get_mut();
+        storage.set_change_tick(change_tick);
+        storage.remove(entity);
+    }
+    fn clear_removed_before(&mut self, tick: u64) {
+        self.get_mut().clear_removed_before(tick);
+    }
+    // Fails instead of panicking while a system holds the storage mutably
+    fn contains_entity(&self, entity: Entity) -> Result<bool, QueryError> {
+        match self.try_borrow() {
+            Ok(storage) => Ok(storage.contains(entity)),
+            Err(_) => Err(QueryError::AlreadyBorrowed(std::any::type_name::<T>())),
+        }
+    }
+    fn component_type_name(&self) -> &'static str {
+        std::any::type_name::<T>()
     }
 }
 // Resources are unique objects (one per application) that may be accessed from world at any time. The idea
@@ -48,94 +72,280 @@ impl<T: 'static> Resource for RefCell<T> {
 
 // Need to define world that will hold all the entities and components
 pub struct World {
-    entities_count: usize,
-    components: Vec<Box<dyn Component>>,
+    entities: Entities,
+    // Keyed by the TypeId of the component type (not the storage) for constant time lookups
+    components: HashMap<TypeId, Box<dyn Component>>,
     resources: HashMap<TypeId, Box<dyn Resource>>,
-    dt: f32,
+    // Change detection, every write to a component is stamped with change_tick. Systems compare against
+    // last_change_tick, which the Application sets to the tick the running system last ran at
+    change_tick: u64,
+    last_change_tick: u64,
+    last_tracker_clear: u64,
+    // One entry per event type registered with add_event, flips that type's Events buffers
+    event_updaters: HashMap<TypeId, fn(&World)>,
+    // Structural changes queued by systems through Commands, waiting for apply_commands
+    command_queue: RefCell<Vec<Command>>,
 }
 
 impl World {
     pub fn new() -> Self {
         World {
-            entities_count: 0,
-            components: Vec::new(),
+            entities: Entities::new(),
+            components: HashMap::new(),
             resources: HashMap::new(),
-            dt: 0.0,
+            change_tick: 1,
+            last_change_tick: 0,
+            last_tracker_clear: 0,
+            event_updaters: HashMap::new(),
+            command_queue: RefCell::new(Vec::new()),
+        }
+    }
+    pub fn new_entity(&mut self) -> Entity {
+        // Storages are sparse and grow on insert, so nothing needs to be pushed for the new entity
+        self.entities.allocate()
+    }
+    // Removes every component of the entity and frees its index for reuse. Returns false for stale handles
+    pub fn despawn(&mut self, entity: Entity) -> bool {
+        if !self.entities.free(entity) {
+            log::warn!("Attempted to despawn stale entity {:?}", entity);
+            return false;
+        }
+        for component in self.components.values_mut() {
+            component.remove_entity(entity, self.change_tick);
         }
+        true
     }
-    pub fn new_entity(&mut self) -> usize {
-        let entity_id = self.entities_count;
-        for component in self.components.iter_mut() {
-            component.push_none();
+    pub fn is_alive(&self, entity: Entity) -> bool {
+        self.entities.is_alive(entity)
+    }
+    // Only needs &World so it can be used while queries are alive
+    pub fn commands(&self) -> Commands<'_> {
+        Commands::new(&self.entities, self.command_queue.borrow_mut())
+    }
+    // Applies every queued command in order, including any queued by the commands themselves
+    pub fn apply_commands(&mut self) {
+        loop {
+            // Entities reserved by Commands::spawn become alive before anything is inserted into them
+            self.entities.flush();
+            let queue = std::mem::take(self.command_queue.get_mut());
+            if queue.is_empty() {
+                break;
+            }
+            for command in queue {
+                command(self);
+            }
         }
-        self.entities_count += 1;
-        entity_id
+    }
+    // Every entity currently alive in the World
+    pub fn entities(&self) -> Vec<Entity> {
+        self.entities.iter().collect()
     }
 
     pub fn add_component_to_entity<ComponentType: 'static>(
         &mut self,
-        entity: usize,
+        entity: Entity,
         component: ComponentType,
     ) {
-        // Iterate through all of the available components until one matches the provided componentType
-        for component_vec in self.components.iter_mut() {
-            if let Some(component_vec) = component_vec
-                .component_as_any_mut()
-                .downcast_mut::<RefCell<Vec<Option<ComponentType>>>>()
-            {
-                // For that Component Vector make an entry switch None for Some at location EntityId
-                component_vec.borrow_mut()[entity] = Some(component);
-                return;
-            }
+        // A stale handle would write into whichever entity recycled the index
+        if !self.entities.is_alive(entity) {
+            log::warn!("Attempted to add a component to stale entity {:?}", entity);
+            return;
         }
-        let mut new_component: Vec<Option<ComponentType>> = Vec::with_capacity(self.entities_count);
-
-        // Populate the new component with None for all entites as it was just created
-        for _ in 0..self.entities_count {
-            new_component.push(None);
+        self.storage_mut::<ComponentType>()
+            .insert(entity, component);
+    }
+    // Returns the removed component so it can be added to another entity. A removed ModelComponent can just be
+    // dropped, the renderer frees its GPU buffers once no entity holds it anymore
+    pub fn remove_component<ComponentType: 'static>(
+        &mut self,
+        entity: Entity,
+    ) -> Option<ComponentType> {
+        if !self.entities.is_alive(entity) {
+            log::warn!(
+                "Attempted to remove a component from stale entity {:?}",
+                entity
+            );
+            return None;
         }
-
-        // Add Entity to newly created Component
-        new_component[entity] = Some(component);
-
-        // Append new Component to the Vector of pointers (Box) stored in world
-        self.components.push(Box::new(RefCell::new(new_component)));
+        let change_tick = self.change_tick;
+        let storage = self
+            .components
+            .get_mut(&TypeId::of::<ComponentType>())?
+            .component_as_any_mut()
+            .downcast_mut::<RefCell<ComponentStorage<ComponentType>>>()?
+            .get_mut();
+        storage.set_change_tick(change_tick);
+        storage.remove(entity)
+    }
+    // Errors if the storage is mutably borrowed, e.g. by a query alive in the calling system
+    pub fn has_component<ComponentType: 'static>(
+        &self,
+        entity: Entity,
+    ) -> Result<bool, QueryError> {
+        match self.components.get(&TypeId::of::<ComponentType>()) {
+            Some(component) => component.contains_entity(entity),
+            None => Ok(false),
+        }
+    }
+    pub fn get_component<ComponentType: 'static>(
+        &self,
+        entity: Entity,
+    ) -> Option<Ref<'_, ComponentType>> {
+        let storage = self.borrow_component::<ComponentType>()?;
+        Ref::filter_map(storage, |storage| storage.get(entity)).ok()
+    }
+    // Counts as a change for change detection
+    pub fn get_component_mut<ComponentType: 'static>(
+        &self,
+        entity: Entity,
+    ) -> Option<RefMut<'_, ComponentType>> {
+        let storage = self.borrow_component_mut::<ComponentType>()?;
+        RefMut::filter_map(storage, |storage| storage.get_mut(entity)).ok()
+    }
+    // type_name of every component the entity has, sorted so tooling output is stable. Errors with the first
+    // storage that is mutably borrowed
+    pub fn component_names(&self, entity: Entity) -> Result<Vec<&'static str>, QueryError> {
+        if !self.entities.is_alive(entity) {
+            return Ok(Vec::new());
+        }
+        let mut names: Vec<&'static str> = Vec::new();
+        for component in self.components.values() {
+            if component.contains_entity(entity)? {
+                names.push(component.component_type_name());
+            }
+        }
+        names.sort_unstable();
+        Ok(names)
     }
-    // Creates a function to insert an existing component Vector into World
+    // Creates a function to insert an existing component Vector into World, indexed by entity index
     pub fn insert_component<ComponentType: 'static>(
         &mut self,
         component: Vec<Option<ComponentType>>,
     ) {
-        // All the component Vectors need to be the same length, if we have a mismatched number of entities, fail
+        // The Vector must have an entry for every entity slot, if we have a mismatched number of entities, fail
         // Note: Arrays indexed at 0
-        assert_eq!(self.entities_count, component.len());
-        self.components.push(Box::new(RefCell::new(component)));
+        assert_eq!(self.entities.len(), component.len());
+        let entities: Vec<Option<Entity>> = (0..component.len())
+            .map(|index| self.entities.entity_at(index))
+            .collect();
+        let storage = self.storage_mut::<ComponentType>();
+        for (index, value) in component.into_iter().enumerate() {
+            if let Some(value) = value {
+                match entities[index] {
+                    Some(entity) => {
+                        storage.insert(entity, value);
+                    }
+                    None => log::warn!("Dropping component for despawned entity index {}", index),
+                }
+            }
+        }
+    }
+    // Storage for ComponentType, created the first time the component type is used
+    fn storage_mut<ComponentType: 'static>(&mut self) -> &mut ComponentStorage<ComponentType> {
+        let change_tick = self.change_tick;
+        let storage = self
+            .components
+            .entry(TypeId::of::<ComponentType>())
+            .or_insert_with(|| Box::new(RefCell::new(ComponentStorage::<ComponentType>::new())))
+            .component_as_any_mut()
+            .downcast_mut::<RefCell<ComponentStorage<ComponentType>>>()
+            .unwrap()
+            .get_mut();
+        storage.set_change_tick(change_tick);
+        storage
+    }
+    // Finds the storage for ComponentType without borrowing it
+    pub(crate) fn component_cell<ComponentType: 'static>(
+        &self,
+    ) -> Option<&RefCell<ComponentStorage<ComponentType>>> {
+        self.components
+            .get(&TypeId::of::<ComponentType>())?
+            .component_as_any()
+            .downcast_ref::<RefCell<ComponentStorage<ComponentType>>>()
     }
     pub fn borrow_component_mut<ComponentType: 'static>(
         &self,
-    ) -> Option<RefMut<Vec<Option<ComponentType>>>> {
-        for component_vec in self.components.iter() {
-            if let Some(component_vec) = component_vec
-                .component_as_any()
-                .downcast_ref::<RefCell<Vec<Option<ComponentType>>>>()
-            {
-                return Some(component_vec.borrow_mut());
-            }
-        }
-        None
+    ) -> Option<RefMut<ComponentStorage<ComponentType>>> {
+        self.component_cell::<ComponentType>().map(|component_vec| {
+            let mut storage = component_vec.borrow_mut();
+            // Writes through this borrow are stamped with the current tick
+            storage.set_change_tick(self.change_tick);
+            storage
+        })
     }
     pub fn borrow_component<ComponentType: 'static>(
         &self,
-    ) -> Option<Ref<Vec<Option<ComponentType>>>> {
-        for component_vec in self.components.iter() {
-            if let Some(component_vec) = component_vec
-                .component_as_any()
-                .downcast_ref::<RefCell<Vec<Option<ComponentType>>>>()
-            {
-                return Some(component_vec.borrow());
-            }
+    ) -> Option<Ref<ComponentStorage<ComponentType>>> {
+        self.component_cell::<ComponentType>()
+            .map(|component_vec| component_vec.borrow())
+    }
+    // Borrows every component in Q up front, see query.rs
+    pub fn query<Q: QueryData>(&self) -> Result<Query<'_, Q>, QueryError> {
+        Query::new(self)
+    }
+    pub fn query_filtered<Q: QueryData, F: QueryFilter>(
+        &self,
+    ) -> Result<Query<'_, Q, F>, QueryError> {
+        Query::new(self)
+    }
+    // Entities that lost a ComponentType since the running system last ran
+    pub fn removed<ComponentType: 'static>(&self) -> Vec<Entity> {
+        match self.borrow_component::<ComponentType>() {
+            Some(storage) => storage.removed_since(self.last_change_tick).collect(),
+            None => Vec::new(),
+        }
+    }
+    pub fn change_tick(&self) -> u64 {
+        self.change_tick
+    }
+    pub fn last_change_tick(&self) -> u64 {
+        self.last_change_tick
+    }
+    pub fn set_last_change_tick(&mut self, last_change_tick: u64) {
+        self.last_change_tick = last_change_tick;
+    }
+    // Called after every system run, returns the tick that system ran at so it can be handed back to
+    // set_last_change_tick the next time the system runs
+    pub fn increment_change_tick(&mut self) -> u64 {
+        let tick = self.change_tick;
+        self.change_tick += 1;
+        tick
+    }
+    // Called once per frame. Removals are kept for a full frame so every system gets to see them once
+    pub fn clear_trackers(&mut self) {
+        for component in self.components.values_mut() {
+            component.clear_removed_before(self.last_tracker_clear);
+        }
+        self.last_tracker_clear = self.change_tick;
+    }
+    // Registers Events<T> as a resource and has it updated every frame by update_events. Registering the same
+    // type twice keeps the events already sent
+    pub fn add_event<T: 'static>(&mut self) {
+        if self.event_updaters.contains_key(&TypeId::of::<T>()) {
+            return;
+        }
+        self.insert_resource(Events::<T>::new());
+        self.event_updaters
+            .insert(TypeId::of::<T>(), update_events::<T>);
+    }
+    // Called once per frame, events sent the frame before last are dropped
+    pub fn update_events(&mut self) {
+        for updater in self.event_updaters.values() {
+            updater(self);
+        }
+    }
+    // None if T was never registered with add_event
+    pub fn event_writer<T: 'static>(&self) -> Option<EventWriter<'_, T>> {
+        EventWriter::new(self)
+    }
+    pub fn send_event<T: 'static>(&self, event: T) {
+        match self.event_writer::<T>() {
+            Some(mut writer) => writer.send(event),
+            None => log::warn!(
+                "Sent event {} that was never added to the World",
+                std::any::type_name::<T>()
+            ),
         }
-        None
     }
     // At this time this method is limited to one entry per type
     // TODO: Make key/value pair capable of handling multiple inputs of the same type (ie change the key)
@@ -146,10 +356,14 @@ impl World {
         //self.resources.insert(type_id, (&mut resource as *mut R).cast::<u8>());
         self.resources.insert(type_id, boxed_resource);
     }
+    pub fn contains_resource<R: 'static>(&self) -> bool {
+        self.resources.contains_key(&TypeId::of::<R>())
+    }
     // The + 'static lets the program know that the type provided will be valid for the duration of the program and not a reference
+    // None if the resource was never inserted, it's up to the caller whether that's an error
     pub fn get_resource<R: 'static>(&self) -> Option<Ref<R>> {
         let type_id = TypeId::of::<R>();
-        let boxed_resource = self.resources.get(&type_id).unwrap();
+        let boxed_resource = self.resources.get(&type_id)?;
         if let Some(resource) = boxed_resource.resource_as().downcast_ref::<RefCell<R>>() {
             return Some(resource.borrow());
         } else {
@@ -174,13 +388,6 @@ impl World {
         }
     }
 
-    pub fn update_dt(&mut self, dt: f32) {
-        self.dt = dt;
-    }
-    pub fn get_dt(&self) -> f32 {
-        self.dt
-    }
-
     /*
         Old Resource Code attempting to Use Raw pointers, led to Errors when the memory being allocated was on the stack
         and dereferencing the pointers (whose values had been dropped by that time) led to a segmentation fault. I don't
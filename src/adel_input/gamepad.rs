@@ -0,0 +1,494 @@
+use crate::adel_ecs::{RunStage, System, SystemResult, World};
+use crate::adel_input::{parse_float, quote_recorded, InputConsumer, InputPlayback};
+use anyhow::Context;
+use nalgebra::Vector2;
+use std::cell::RefCell;
+use std::collections::{BTreeMap, HashMap, HashSet, VecDeque};
+use std::fmt::Write as _;
+use std::rc::Rc;
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct GamepadId(pub usize);
+
+// Named by position so they mean the same thing on every controller, South is A on an Xbox pad and Cross on a
+// PlayStation one
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum GamepadButton {
+    South,
+    East,
+    North,
+    West,
+    LeftBumper,
+    RightBumper,
+    LeftTrigger,
+    RightTrigger,
+    Select,
+    Start,
+    Mode,
+    LeftThumb,
+    RightThumb,
+    DPadUp,
+    DPadDown,
+    DPadLeft,
+    DPadRight,
+}
+
+impl GamepadButton {
+    pub const ALL: [GamepadButton; 17] = [
+        GamepadButton::South,
+        GamepadButton::East,
+        GamepadButton::North,
+        GamepadButton::West,
+        GamepadButton::LeftBumper,
+        GamepadButton::RightBumper,
+        GamepadButton::LeftTrigger,
+        GamepadButton::RightTrigger,
+        GamepadButton::Select,
+        GamepadButton::Start,
+        GamepadButton::Mode,
+        GamepadButton::LeftThumb,
+        GamepadButton::RightThumb,
+        GamepadButton::DPadUp,
+        GamepadButton::DPadDown,
+        GamepadButton::DPadLeft,
+        GamepadButton::DPadRight,
+    ];
+
+    // Names are the variant names, same as Debug prints
+    pub fn from_name(name: &str) -> Option<Self> {
+        Self::ALL
+            .into_iter()
+            .find(|button| format!("{:?}", button) == name)
+    }
+}
+
+// Sticks go from -1 to 1 with up and right positive, triggers from 0 to 1
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum GamepadAxis {
+    LeftStickX,
+    LeftStickY,
+    RightStickX,
+    RightStickY,
+    LeftTrigger,
+    RightTrigger,
+}
+
+impl GamepadAxis {
+    pub const ALL: [GamepadAxis; 6] = [
+        GamepadAxis::LeftStickX,
+        GamepadAxis::LeftStickY,
+        GamepadAxis::RightStickX,
+        GamepadAxis::RightStickY,
+        GamepadAxis::LeftTrigger,
+        GamepadAxis::RightTrigger,
+    ];
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        Self::ALL
+            .into_iter()
+            .find(|axis| format!("{:?}", axis) == name)
+    }
+}
+
+// What an InputSource reports, also sent as an event so systems can react to controllers coming and going
+#[derive(Debug, Clone, PartialEq)]
+pub enum GamepadEvent {
+    Connected {
+        id: GamepadId,
+        name: String,
+    },
+    Disconnected {
+        id: GamepadId,
+    },
+    ButtonPressed {
+        id: GamepadId,
+        button: GamepadButton,
+    },
+    ButtonReleased {
+        id: GamepadId,
+        button: GamepadButton,
+    },
+    AxisChanged {
+        id: GamepadId,
+        axis: GamepadAxis,
+        value: f32,
+    },
+}
+
+// A backend for input winit doesn't deliver. Sources are polled once per frame at the start of EarlyUpdate and
+// whatever they report lands in the InputConsumer. Add them to the InputSources resource
+pub trait InputSource {
+    fn poll(&mut self, events: &mut Vec<GamepadEvent>);
+    fn name(&self) -> &str {
+        std::any::type_name::<Self>()
+    }
+}
+
+// Source that reports whatever was pushed through its handle, for tests and tools on machines without controllers
+pub struct SyntheticInputSource {
+    queue: Rc<RefCell<VecDeque<GamepadEvent>>>,
+}
+
+impl SyntheticInputSource {
+    pub fn new() -> (Self, SyntheticInputHandle) {
+        let queue = Rc::new(RefCell::new(VecDeque::new()));
+        (
+            Self {
+                queue: queue.clone(),
+            },
+            SyntheticInputHandle { queue },
+        )
+    }
+}
+
+impl InputSource for SyntheticInputSource {
+    fn poll(&mut self, events: &mut Vec<GamepadEvent>) {
+        events.extend(self.queue.borrow_mut().drain(..));
+    }
+}
+
+// Events pushed here are reported the next time the SyntheticInputSource is polled
+#[derive(Clone)]
+pub struct SyntheticInputHandle {
+    queue: Rc<RefCell<VecDeque<GamepadEvent>>>,
+}
+
+impl SyntheticInputHandle {
+    pub fn send(&self, event: GamepadEvent) {
+        self.queue.borrow_mut().push_back(event);
+    }
+    pub fn connect(&self, id: GamepadId, name: &str) {
+        self.send(GamepadEvent::Connected {
+            id,
+            name: name.to_owned(),
+        });
+    }
+    pub fn disconnect(&self, id: GamepadId) {
+        self.send(GamepadEvent::Disconnected { id });
+    }
+    pub fn press(&self, id: GamepadId, button: GamepadButton) {
+        self.send(GamepadEvent::ButtonPressed { id, button });
+    }
+    pub fn release(&self, id: GamepadId, button: GamepadButton) {
+        self.send(GamepadEvent::ButtonReleased { id, button });
+    }
+    pub fn set_axis(&self, id: GamepadId, axis: GamepadAxis, value: f32) {
+        self.send(GamepadEvent::AxisChanged { id, axis, value });
+    }
+}
+
+// Resource holding every InputSource, polled in the order they were added
+#[derive(Default)]
+pub struct InputSources {
+    sources: Vec<Box<dyn InputSource>>,
+}
+
+impl InputSources {
+    pub fn new() -> Self {
+        Self::default()
+    }
+    pub fn add(&mut self, source: impl InputSource + 'static) -> &mut Self {
+        log::info!("Added input source {}", source.name());
+        self.sources.push(Box::new(source));
+        self
+    }
+    pub fn len(&self) -> usize {
+        self.sources.len()
+    }
+    pub fn is_empty(&self) -> bool {
+        self.sources.is_empty()
+    }
+    fn poll(&mut self, events: &mut Vec<GamepadEvent>) {
+        for source in self.sources.iter_mut() {
+            source.poll(events);
+        }
+    }
+}
+
+// State of one connected controller. Stick and trigger values below the dead zone read as 0 and the rest of
+// the range is stretched back out to 1, so worn sticks that don't center perfectly don't drift
+#[derive(Debug, Clone)]
+pub struct GamepadState {
+    name: String,
+    buttons: HashSet<GamepadButton>,
+    just_pressed: HashSet<GamepadButton>,
+    just_released: HashSet<GamepadButton>,
+    axes: HashMap<GamepadAxis, f32>,
+    dead_zone: f32,
+}
+
+impl GamepadState {
+    pub const DEFAULT_DEAD_ZONE: f32 = 0.15;
+
+    pub(crate) fn new(name: String) -> Self {
+        Self {
+            name,
+            buttons: HashSet::new(),
+            just_pressed: HashSet::new(),
+            just_released: HashSet::new(),
+            axes: HashMap::new(),
+            dead_zone: Self::DEFAULT_DEAD_ZONE,
+        }
+    }
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+    pub fn dead_zone(&self) -> f32 {
+        self.dead_zone
+    }
+    pub fn set_dead_zone(&mut self, dead_zone: f32) {
+        self.dead_zone = dead_zone.clamp(0.0, 0.99);
+    }
+    pub fn is_pressed(&self, button: GamepadButton) -> bool {
+        self.buttons.contains(&button)
+    }
+    pub fn is_just_pressed(&self, button: GamepadButton) -> bool {
+        self.just_pressed.contains(&button)
+    }
+    pub fn is_just_released(&self, button: GamepadButton) -> bool {
+        self.just_released.contains(&button)
+    }
+    // Dead zone applied to the axis on its own
+    pub fn axis(&self, axis: GamepadAxis) -> f32 {
+        apply_dead_zone(self.raw_axis(axis), self.dead_zone)
+    }
+    // The value the controller reported
+    pub fn raw_axis(&self, axis: GamepadAxis) -> f32 {
+        self.axes.get(&axis).copied().unwrap_or(0.0)
+    }
+    // Sticks get a radial dead zone so pushing diagonally isn't snapped to the axes
+    pub fn left_stick(&self) -> Vector2<f32> {
+        self.stick(GamepadAxis::LeftStickX, GamepadAxis::LeftStickY)
+    }
+    pub fn right_stick(&self) -> Vector2<f32> {
+        self.stick(GamepadAxis::RightStickX, GamepadAxis::RightStickY)
+    }
+    fn stick(&self, x: GamepadAxis, y: GamepadAxis) -> Vector2<f32> {
+        let stick = Vector2::new(self.raw_axis(x), self.raw_axis(y));
+        let length = stick.norm();
+        if length <= self.dead_zone {
+            return Vector2::zeros();
+        }
+        stick / length * apply_dead_zone(length.min(1.0), self.dead_zone)
+    }
+    pub(crate) fn capture(&mut self, event: &GamepadEvent) {
+        match event {
+            GamepadEvent::ButtonPressed { button, .. } => {
+                if self.buttons.insert(*button) {
+                    self.just_pressed.insert(*button);
+                }
+            }
+            GamepadEvent::ButtonReleased { button, .. } => {
+                if self.buttons.remove(button) {
+                    self.just_released.insert(*button);
+                }
+            }
+            GamepadEvent::AxisChanged { axis, value, .. } => {
+                self.axes.insert(*axis, *value);
+            }
+            GamepadEvent::Connected { .. } | GamepadEvent::Disconnected { .. } => {}
+        }
+    }
+    pub(crate) fn end_frame(&mut self) {
+        self.just_pressed.clear();
+        self.just_released.clear();
+    }
+    // Lines for InputConsumer::write_recorded, everything after the pad line is prefixed with pad_ and the id
+    pub(crate) fn write_recorded(&self, id: GamepadId, out: &mut String) {
+        let _ = writeln!(
+            out,
+            "pad {} {} {}",
+            id.0,
+            self.dead_zone,
+            quote_recorded(&self.name)
+        );
+        for (field, buttons) in [
+            ("buttons", &self.buttons),
+            ("down", &self.just_pressed),
+            ("up", &self.just_released),
+        ] {
+            if !buttons.is_empty() {
+                let mut names: Vec<String> = buttons
+                    .iter()
+                    .map(|button| format!("{:?}", button))
+                    .collect();
+                names.sort();
+                let _ = writeln!(out, "pad_{} {} {}", field, id.0, names.join(" "));
+            }
+        }
+        if !self.axes.is_empty() {
+            let mut axes: Vec<String> = self
+                .axes
+                .iter()
+                .map(|(axis, value)| format!("{:?} {}", axis, value))
+                .collect();
+            axes.sort();
+            let _ = writeln!(out, "pad_axes {} {}", id.0, axes.join(" "));
+        }
+    }
+    pub(crate) fn read_recorded(&mut self, field: &str, values: &[&str]) -> Result<(), String> {
+        let buttons = || {
+            values
+                .iter()
+                .map(|name| {
+                    GamepadButton::from_name(name)
+                        .ok_or_else(|| format!("unknown gamepad button {}", name))
+                })
+                .collect::<Result<HashSet<_>, _>>()
+        };
+        match field {
+            "buttons" => self.buttons = buttons()?,
+            "down" => self.just_pressed = buttons()?,
+            "up" => self.just_released = buttons()?,
+            "axes" => {
+                let pairs = values.chunks_exact(2);
+                if !pairs.remainder().is_empty() {
+                    return Err("expected pairs of <axis> <value>".to_owned());
+                }
+                for pair in pairs {
+                    let axis = GamepadAxis::from_name(pair[0])
+                        .ok_or_else(|| format!("unknown gamepad axis {}", pair[0]))?;
+                    self.axes.insert(axis, parse_float(pair[1])?);
+                }
+            }
+            _ => return Err(format!("unknown gamepad field pad_{}", field)),
+        }
+        Ok(())
+    }
+}
+
+fn playing_back(world: &World) -> bool {
+    world.contains_resource::<InputPlayback>()
+        && world.get_resource::<InputPlayback>().unwrap().is_playing()
+}
+
+fn apply_dead_zone(value: f32, dead_zone: f32) -> f32 {
+    if value.abs() <= dead_zone {
+        return 0.0;
+    }
+    value.signum() * (value.abs() - dead_zone) / (1.0 - dead_zone)
+}
+
+// Polls every InputSource and hands what they report to the InputConsumer and to the GamepadEvent readers
+pub struct InputSourceSystem {
+    name: &'static str,
+    events: Vec<GamepadEvent>,
+    // What the live controllers last reported, kept through a playback so they can be connected again once it's
+    // over with the buttons and axes they hold at that point
+    live: BTreeMap<GamepadId, GamepadState>,
+    was_playing_back: bool,
+}
+
+impl InputSourceSystem {
+    pub fn new() -> Self {
+        Self {
+            name: "InputSourceSystem",
+            events: Vec::new(),
+            live: BTreeMap::new(),
+            was_playing_back: false,
+        }
+    }
+    // The InputConsumer still holds the recorded controllers, they are disconnected and the live ones connected
+    // again ahead of this frame's events, followed by whatever they were holding
+    fn reconnect_live_gamepads(&mut self, input_consumer: &InputConsumer) {
+        let mut reconnect: Vec<GamepadEvent> = input_consumer
+            .gamepads()
+            .map(|(id, _)| GamepadEvent::Disconnected { id })
+            .collect();
+        for (id, gamepad) in self.live.iter() {
+            let id = *id;
+            reconnect.push(GamepadEvent::Connected {
+                id,
+                name: gamepad.name().to_owned(),
+            });
+            let pressed = GamepadButton::ALL
+                .into_iter()
+                .filter(|button| gamepad.is_pressed(*button))
+                .map(|button| GamepadEvent::ButtonPressed { id, button });
+            let moved = GamepadAxis::ALL
+                .into_iter()
+                .filter(|axis| gamepad.raw_axis(*axis) != 0.0)
+                .map(|axis| GamepadEvent::AxisChanged {
+                    id,
+                    axis,
+                    value: gamepad.raw_axis(axis),
+                });
+            reconnect.extend(pressed.chain(moved));
+        }
+        self.events.splice(0..0, reconnect);
+    }
+    fn track_live_gamepads(&mut self) {
+        for event in self.events.iter() {
+            match event {
+                GamepadEvent::Connected { id, name } => {
+                    self.live.insert(*id, GamepadState::new(name.clone()));
+                }
+                GamepadEvent::Disconnected { id } => {
+                    self.live.remove(id);
+                }
+                GamepadEvent::ButtonPressed { id, .. }
+                | GamepadEvent::ButtonReleased { id, .. }
+                | GamepadEvent::AxisChanged { id, .. } => {
+                    if let Some(gamepad) = self.live.get_mut(id) {
+                        gamepad.capture(event);
+                    }
+                }
+            }
+        }
+        for gamepad in self.live.values_mut() {
+            gamepad.end_frame();
+        }
+    }
+}
+
+impl System for InputSourceSystem {
+    fn startup(&mut self, _world: &mut World) -> SystemResult {
+        Ok(())
+    }
+    fn run(&mut self, world: &mut World) -> SystemResult {
+        world
+            .get_resource_mut::<InputSources>()
+            .context("InputSourceSystem needs the InputSources resource, add the InputPlugin")?
+            .poll(&mut self.events);
+        // A playback replaces the gamepads along with everything else, live input is only tracked and dropped
+        if playing_back(world) {
+            self.track_live_gamepads();
+            self.was_playing_back = true;
+            self.events.clear();
+            return Ok(());
+        }
+        if std::mem::take(&mut self.was_playing_back) {
+            let input_consumer = world.get_resource::<InputConsumer>().context(
+                "InputSourceSystem needs the InputConsumer resource, add the InputPlugin",
+            )?;
+            self.reconnect_live_gamepads(&input_consumer);
+        }
+        // The reconnection leaves the live controllers the way they were, tracking it changes nothing
+        self.track_live_gamepads();
+        if self.events.is_empty() {
+            return Ok(());
+        }
+        {
+            let mut input_consumer = world.get_resource_mut::<InputConsumer>().context(
+                "InputSourceSystem needs the InputConsumer resource, add the InputPlugin",
+            )?;
+            for event in self.events.iter() {
+                input_consumer.capture_gamepad_event(event);
+            }
+        }
+        if let Some(mut writer) = world.event_writer::<GamepadEvent>() {
+            writer.send_batch(self.events.iter().cloned());
+        }
+        self.events.clear();
+        Ok(())
+    }
+    fn shutdown(&mut self, _world: &mut World) -> SystemResult {
+        Ok(())
+    }
+    fn name(&self) -> &str {
+        self.name
+    }
+    fn get_run_stage(&self) -> RunStage {
+        RunStage::EarlyUpdate
+    }
+}
@@ -1,23 +1,355 @@
-use std::collections::HashSet;
-use winit::event::{ElementState, KeyboardInput, VirtualKeyCode};
+use crate::adel_input::{
+    key_from_name, mouse_button_from_name, mouse_button_name, GamepadAxis, GamepadButton,
+    GamepadEvent, GamepadId, GamepadState,
+};
+use nalgebra::Vector2;
+use std::collections::{BTreeMap, HashSet};
+use std::fmt::Write as _;
+use winit::dpi::{PhysicalPosition, PhysicalSize};
+use winit::event::{ElementState, KeyboardInput, MouseButton, MouseScrollDelta, VirtualKeyCode};
 
-#[derive(Debug, Clone)]
+// Pixel scroll deltas (touchpads) are turned into lines so systems only deal with one unit
+const PIXELS_PER_SCROLL_LINE: f32 = 20.0;
+
+// Keyboard, mouse and gamepad state captured from the window events and InputSources. Positions and sizes are
+// in physical pixels with the origin at the top left of the window. mouse_delta, scroll_delta and the just
+// pressed/released sets cover everything since the last frame and are cleared by the Application at the end of every frame
+#[derive(Debug, Clone, Default)]
 pub struct InputConsumer {
     pub pressed: HashSet<VirtualKeyCode>,
+    just_pressed: HashSet<VirtualKeyCode>,
+    just_released: HashSet<VirtualKeyCode>,
+    mouse_buttons: HashSet<MouseButton>,
+    mouse_just_pressed: HashSet<MouseButton>,
+    mouse_just_released: HashSet<MouseButton>,
+    // None while the cursor is outside of the window
+    cursor_position: Option<Vector2<f32>>,
+    window_size: Vector2<f32>,
+    mouse_delta: Vector2<f32>,
+    scroll_delta: Vector2<f32>,
+    // Device events keep coming while another window has focus, they're ignored until focus comes back
+    unfocused: bool,
+    // Connected gamepads, ordered so the first connected one is the first one iterated
+    gamepads: BTreeMap<GamepadId, GamepadState>,
 }
 
 impl InputConsumer {
+    pub fn new() -> Self {
+        Self::default()
+    }
+    // winit 0.28 doesn't flag key repeats, they show up as more Pressed events for a key that is already held
+    // and are left out of just_pressed
     pub fn capture_keyboard_input(&mut self, keyboard_input: &KeyboardInput) {
         let key_code = keyboard_input.virtual_keycode.unwrap();
         match keyboard_input.state {
             ElementState::Pressed => {
-                self.pressed.insert(key_code.clone());
+                if self.pressed.insert(key_code) {
+                    self.just_pressed.insert(key_code);
+                }
+            }
+            ElementState::Released => {
+                if self.pressed.remove(&key_code) {
+                    self.just_released.insert(key_code);
+                }
+            }
+        };
+    }
+    pub fn capture_mouse_button(&mut self, state: ElementState, button: MouseButton) {
+        match state {
+            ElementState::Pressed => {
+                if self.mouse_buttons.insert(button) {
+                    self.mouse_just_pressed.insert(button);
+                }
             }
             ElementState::Released => {
-                if self.pressed.contains(&key_code) {
-                    self.pressed.remove(&key_code);
+                if self.mouse_buttons.remove(&button) {
+                    self.mouse_just_released.insert(button);
                 }
             }
+        }
+    }
+    pub fn capture_cursor_moved(&mut self, position: PhysicalPosition<f64>) {
+        self.cursor_position = Some(Vector2::new(position.x as f32, position.y as f32));
+    }
+    pub fn capture_cursor_left(&mut self) {
+        self.cursor_position = None;
+    }
+    // Raw motion from DeviceEvent::MouseMotion, keeps coming when the cursor is stuck at the edge of the window
+    // or grabbed, which makes it the one to use for mouse-look
+    pub fn capture_mouse_motion(&mut self, delta: (f64, f64)) {
+        if self.unfocused {
+            return;
+        }
+        self.mouse_delta += Vector2::new(delta.0 as f32, delta.1 as f32);
+    }
+    pub fn capture_mouse_wheel(&mut self, delta: MouseScrollDelta) {
+        self.scroll_delta += match delta {
+            MouseScrollDelta::LineDelta(x, y) => Vector2::new(x, y),
+            MouseScrollDelta::PixelDelta(position) => {
+                Vector2::new(position.x as f32, position.y as f32) / PIXELS_PER_SCROLL_LINE
+            }
         };
     }
+    // Releases aren't reported to a window without focus, so everything held is let go of when it's lost
+    pub fn capture_focus(&mut self, focused: bool) {
+        self.unfocused = !focused;
+        if !focused {
+            self.just_released.extend(self.pressed.drain());
+            self.mouse_just_released.extend(self.mouse_buttons.drain());
+        }
+    }
+    pub fn capture_gamepad_event(&mut self, event: &GamepadEvent) {
+        match event {
+            GamepadEvent::Connected { id, name } => {
+                log::info!("Gamepad {:?} connected: {}", id, name);
+                self.gamepads.insert(*id, GamepadState::new(name.clone()));
+            }
+            GamepadEvent::Disconnected { id } => {
+                log::info!("Gamepad {:?} disconnected", id);
+                self.gamepads.remove(id);
+            }
+            GamepadEvent::ButtonPressed { id, .. }
+            | GamepadEvent::ButtonReleased { id, .. }
+            | GamepadEvent::AxisChanged { id, .. } => match self.gamepads.get_mut(id) {
+                Some(gamepad) => gamepad.capture(event),
+                None => log::warn!("Input from gamepad {:?} which never connected", id),
+            },
+        }
+    }
+    pub fn set_window_size(&mut self, size: PhysicalSize<u32>) {
+        self.window_size = Vector2::new(size.width as f32, size.height as f32);
+    }
+    // Called by the Application once every system has seen this frame's input
+    pub fn end_frame(&mut self) {
+        self.just_pressed.clear();
+        self.just_released.clear();
+        self.mouse_just_pressed.clear();
+        self.mouse_just_released.clear();
+        self.mouse_delta = Vector2::zeros();
+        self.scroll_delta = Vector2::zeros();
+        for gamepad in self.gamepads.values_mut() {
+            gamepad.end_frame();
+        }
+    }
+    // The whole state as lines of <field> <values>, leaving out whatever is empty. Used by InputRecording,
+    // read_recorded takes the lines back one at a time
+    pub(crate) fn write_recorded(&self, out: &mut String) {
+        let key_name = |key_code: &VirtualKeyCode| format!("{:?}", key_code);
+        let button_name = |button: &MouseButton| mouse_button_name(*button);
+        write_set(out, "keys", &self.pressed, key_name);
+        write_set(out, "keys_down", &self.just_pressed, key_name);
+        write_set(out, "keys_up", &self.just_released, key_name);
+        write_set(out, "mouse", &self.mouse_buttons, button_name);
+        write_set(out, "mouse_down", &self.mouse_just_pressed, button_name);
+        write_set(out, "mouse_up", &self.mouse_just_released, button_name);
+        if let Some(cursor_position) = self.cursor_position {
+            write_vector(out, "cursor", cursor_position);
+        }
+        write_vector(out, "window", self.window_size);
+        write_vector(out, "motion", self.mouse_delta);
+        write_vector(out, "scroll", self.scroll_delta);
+        if self.unfocused {
+            let _ = writeln!(out, "unfocused");
+        }
+        for (id, gamepad) in self.gamepads.iter() {
+            gamepad.write_recorded(*id, out);
+        }
+    }
+    pub(crate) fn read_recorded(&mut self, field: &str, values: &[&str]) -> Result<(), String> {
+        let key = |name: &str| key_from_name(name).ok_or_else(|| format!("unknown key {}", name));
+        let button = |name: &str| {
+            mouse_button_from_name(name).ok_or_else(|| format!("unknown mouse button {}", name))
+        };
+        match field {
+            "keys" => {
+                self.pressed = values
+                    .iter()
+                    .map(|name| key(name))
+                    .collect::<Result<_, _>>()?
+            }
+            "keys_down" => {
+                self.just_pressed = values
+                    .iter()
+                    .map(|name| key(name))
+                    .collect::<Result<_, _>>()?
+            }
+            "keys_up" => {
+                self.just_released = values
+                    .iter()
+                    .map(|name| key(name))
+                    .collect::<Result<_, _>>()?
+            }
+            "mouse" => {
+                self.mouse_buttons = values
+                    .iter()
+                    .map(|name| button(name))
+                    .collect::<Result<_, _>>()?
+            }
+            "mouse_down" => {
+                self.mouse_just_pressed = values
+                    .iter()
+                    .map(|name| button(name))
+                    .collect::<Result<_, _>>()?
+            }
+            "mouse_up" => {
+                self.mouse_just_released = values
+                    .iter()
+                    .map(|name| button(name))
+                    .collect::<Result<_, _>>()?
+            }
+            "cursor" => self.cursor_position = Some(parse_vector(values)?),
+            "window" => self.window_size = parse_vector(values)?,
+            "motion" => self.mouse_delta = parse_vector(values)?,
+            "scroll" => self.scroll_delta = parse_vector(values)?,
+            "unfocused" => self.unfocused = true,
+            // pad <id> <dead zone> "<name>", the rest of the gamepad's lines come after it
+            "pad" => match values {
+                [id, dead_zone, name] => {
+                    let mut gamepad = GamepadState::new((*name).to_owned());
+                    gamepad.set_dead_zone(parse_float(dead_zone)?);
+                    self.gamepads.insert(parse_gamepad_id(id)?, gamepad);
+                }
+                _ => return Err("expected pad <id> <dead zone> \"<name>\"".to_owned()),
+            },
+            _ if field.starts_with("pad_") => match values {
+                [id, values @ ..] => {
+                    let id = parse_gamepad_id(id)?;
+                    self.gamepads
+                        .get_mut(&id)
+                        .ok_or_else(|| {
+                            format!("{} for gamepad {} before its pad line", field, id.0)
+                        })?
+                        .read_recorded(&field["pad_".len()..], values)?;
+                }
+                _ => return Err(format!("{} is missing the gamepad id", field)),
+            },
+            _ => return Err(format!("unknown input field {}", field)),
+        }
+        Ok(())
+    }
+
+    pub fn is_key_pressed(&self, key_code: VirtualKeyCode) -> bool {
+        self.pressed.contains(&key_code)
+    }
+    // Only true for the frame the key went down in, for actions that should fire once per press
+    pub fn is_key_just_pressed(&self, key_code: VirtualKeyCode) -> bool {
+        self.just_pressed.contains(&key_code)
+    }
+    pub fn is_key_just_released(&self, key_code: VirtualKeyCode) -> bool {
+        self.just_released.contains(&key_code)
+    }
+    pub fn just_pressed(&self) -> &HashSet<VirtualKeyCode> {
+        &self.just_pressed
+    }
+    pub fn just_released(&self) -> &HashSet<VirtualKeyCode> {
+        &self.just_released
+    }
+    pub fn is_mouse_pressed(&self, button: MouseButton) -> bool {
+        self.mouse_buttons.contains(&button)
+    }
+    pub fn is_mouse_just_pressed(&self, button: MouseButton) -> bool {
+        self.mouse_just_pressed.contains(&button)
+    }
+    pub fn is_mouse_just_released(&self, button: MouseButton) -> bool {
+        self.mouse_just_released.contains(&button)
+    }
+    pub fn mouse_buttons(&self) -> &HashSet<MouseButton> {
+        &self.mouse_buttons
+    }
+    pub fn cursor_position(&self) -> Option<Vector2<f32>> {
+        self.cursor_position
+    }
+    // Cursor position from (0, 0) at the top left to (1, 1) at the bottom right of the window
+    pub fn cursor_position_normalized(&self) -> Option<Vector2<f32>> {
+        if self.window_size.x <= 0.0 || self.window_size.y <= 0.0 {
+            return None;
+        }
+        self.cursor_position
+            .map(|position| position.component_div(&self.window_size))
+    }
+    pub fn is_focused(&self) -> bool {
+        !self.unfocused
+    }
+    pub fn mouse_delta(&self) -> Vector2<f32> {
+        self.mouse_delta
+    }
+    // In lines, positive y is scrolling up/away from the user
+    pub fn scroll_delta(&self) -> Vector2<f32> {
+        self.scroll_delta
+    }
+    pub fn gamepad(&self, id: GamepadId) -> Option<&GamepadState> {
+        self.gamepads.get(&id)
+    }
+    pub fn gamepad_mut(&mut self, id: GamepadId) -> Option<&mut GamepadState> {
+        self.gamepads.get_mut(&id)
+    }
+    pub fn gamepads(&self) -> impl Iterator<Item = (GamepadId, &GamepadState)> {
+        self.gamepads.iter().map(|(id, gamepad)| (*id, gamepad))
+    }
+    // The any gamepad versions below are what the InputMap uses, for games with a single player
+    pub fn is_gamepad_pressed(&self, button: GamepadButton) -> bool {
+        self.gamepads
+            .values()
+            .any(|gamepad| gamepad.is_pressed(button))
+    }
+    pub fn is_gamepad_just_pressed(&self, button: GamepadButton) -> bool {
+        self.gamepads
+            .values()
+            .any(|gamepad| gamepad.is_just_pressed(button))
+    }
+    pub fn is_gamepad_just_released(&self, button: GamepadButton) -> bool {
+        self.gamepads
+            .values()
+            .any(|gamepad| gamepad.is_just_released(button))
+    }
+    // The value furthest from 0 over every gamepad
+    pub fn gamepad_axis(&self, axis: GamepadAxis) -> f32 {
+        self.gamepads
+            .values()
+            .map(|gamepad| gamepad.axis(axis))
+            .fold(0.0, |value: f32, axis| {
+                if axis.abs() > value.abs() {
+                    axis
+                } else {
+                    value
+                }
+            })
+    }
+}
+
+// Sorted so the same state always writes the same line
+fn write_set<T>(out: &mut String, field: &str, set: &HashSet<T>, name: impl Fn(&T) -> String) {
+    if set.is_empty() {
+        return;
+    }
+    let mut names: Vec<String> = set.iter().map(name).collect();
+    names.sort();
+    let _ = writeln!(out, "{} {}", field, names.join(" "));
+}
+
+// f32 Display prints the shortest string that parses back to the same value, recordings replay exactly
+fn write_vector(out: &mut String, field: &str, vector: Vector2<f32>) {
+    if vector != Vector2::zeros() {
+        let _ = writeln!(out, "{} {} {}", field, vector.x, vector.y);
+    }
+}
+
+fn parse_vector(values: &[&str]) -> Result<Vector2<f32>, String> {
+    match values {
+        [x, y] => Ok(Vector2::new(parse_float(x)?, parse_float(y)?)),
+        _ => Err(format!("expected an x and a y, found {}", values.join(" "))),
+    }
+}
+
+pub(crate) fn parse_float(value: &str) -> Result<f32, String> {
+    value
+        .parse()
+        .map_err(|_| format!("invalid number {}", value))
+}
+
+fn parse_gamepad_id(value: &str) -> Result<GamepadId, String> {
+    value
+        .parse()
+        .map(GamepadId)
+        .map_err(|_| format!("invalid gamepad id {}", value))
 }
@@ -0,0 +1,672 @@
+use crate::adel_app::AppExit;
+use crate::adel_ecs::{RunStage, System, SystemResult, World};
+use crate::adel_input::{GamepadAxis, GamepadButton, InputConsumer};
+use anyhow::Context;
+use nalgebra::Vector2;
+use std::collections::HashMap;
+use std::fmt;
+use std::fmt::Write as _;
+use std::path::Path;
+use winit::event::{MouseButton, VirtualKeyCode};
+
+// Something that is either held or not
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum ButtonBinding {
+    Key(VirtualKeyCode),
+    Mouse(MouseButton),
+    // Held on any connected gamepad
+    Gamepad(GamepadButton),
+}
+
+impl ButtonBinding {
+    fn pressed(&self, input: &InputConsumer) -> bool {
+        match self {
+            ButtonBinding::Key(key_code) => input.is_key_pressed(*key_code),
+            ButtonBinding::Mouse(button) => input.is_mouse_pressed(*button),
+            ButtonBinding::Gamepad(button) => input.is_gamepad_pressed(*button),
+        }
+    }
+    fn just_pressed(&self, input: &InputConsumer) -> bool {
+        match self {
+            ButtonBinding::Key(key_code) => input.is_key_just_pressed(*key_code),
+            ButtonBinding::Mouse(button) => input.is_mouse_just_pressed(*button),
+            ButtonBinding::Gamepad(button) => input.is_gamepad_just_pressed(*button),
+        }
+    }
+    fn just_released(&self, input: &InputConsumer) -> bool {
+        match self {
+            ButtonBinding::Key(key_code) => input.is_key_just_released(*key_code),
+            ButtonBinding::Mouse(button) => input.is_mouse_just_released(*button),
+            ButtonBinding::Gamepad(button) => input.is_gamepad_just_released(*button),
+        }
+    }
+}
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum AxisInput {
+    // 1 while positive is held, -1 while negative is held, 0 for both or neither
+    Buttons {
+        positive: ButtonBinding,
+        negative: ButtonBinding,
+    },
+    // Mouse motion this frame in pixels, scroll in lines
+    MouseX,
+    MouseY,
+    ScrollX,
+    ScrollY,
+    // Stick or trigger position after the dead zone, from whichever gamepad is pushed furthest
+    Gamepad(GamepadAxis),
+}
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct AxisBinding {
+    pub input: AxisInput,
+    pub scale: f32,
+}
+
+impl AxisBinding {
+    pub fn new(input: AxisInput) -> Self {
+        Self { input, scale: 1.0 }
+    }
+    pub fn buttons(positive: ButtonBinding, negative: ButtonBinding) -> Self {
+        Self::new(AxisInput::Buttons { positive, negative })
+    }
+    pub fn keys(positive: VirtualKeyCode, negative: VirtualKeyCode) -> Self {
+        Self::buttons(ButtonBinding::Key(positive), ButtonBinding::Key(negative))
+    }
+    pub fn with_scale(mut self, scale: f32) -> Self {
+        self.scale = scale;
+        self
+    }
+    fn value(&self, input: &InputConsumer) -> f32 {
+        let value = match self.input {
+            AxisInput::Buttons { positive, negative } => {
+                positive.pressed(input) as i32 as f32 - negative.pressed(input) as i32 as f32
+            }
+            AxisInput::MouseX => input.mouse_delta().x,
+            AxisInput::MouseY => input.mouse_delta().y,
+            AxisInput::ScrollX => input.scroll_delta().x,
+            AxisInput::ScrollY => input.scroll_delta().y,
+            AxisInput::Gamepad(axis) => input.gamepad_axis(axis),
+        };
+        value * self.scale
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct InputMapError {
+    pub line: usize,
+    pub message: String,
+}
+
+impl fmt::Display for InputMapError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "line {}: {}", self.line, self.message)
+    }
+}
+
+impl std::error::Error for InputMapError {}
+
+// Resource mapping named actions and axes to the keys, mouse and gamepad input driving them, so systems ask for
+// "move_forward" instead of checking VirtualKeyCode::W and controls can be rebound without touching them.
+// Actions are buttons, an action is pressed while any of its bindings is. Axes add up all of their bindings and
+// 2D axes pair up two axes. Names nothing is bound to read as released/0.
+//
+// Bindings files have one binding per line, # starts a comment:
+//   action quit = key:Escape
+//   action fire = mouse:Left, key:Space, pad:RightTrigger
+//   axis move_forward = key:W/key:S, pad:LeftStickY
+//   axis look_yaw = key:Right/key:Left, pad:RightStickX*0.5
+//   axis2d move = move_right, move_forward
+// Buttons are key:<VirtualKeyCode>, mouse:Left/Right/Middle/<number> or pad:<GamepadButton>, axis inputs are
+// positive/negative button pairs, mouse_x, mouse_y, scroll_x, scroll_y or pad:<GamepadAxis>, any of them
+// optionally scaled with *<factor>. Buttons and sticks are rates to be multiplied by the frame time, the mouse and
+// scroll inputs are how far they moved this frame. Keep the two on separate axes and read mouse axes once per
+// frame without dt, the look_* axes feed the cameras as rates so the cameras read the mouse on their own
+#[derive(Debug, Clone)]
+pub struct InputMap {
+    actions: HashMap<String, Vec<ButtonBinding>>,
+    axes: HashMap<String, Vec<AxisBinding>>,
+    axes_2d: HashMap<String, (String, String)>,
+}
+
+impl InputMap {
+    // No bindings at all, InputMap::default has the engine's default controls
+    pub fn empty() -> Self {
+        Self {
+            actions: HashMap::new(),
+            axes: HashMap::new(),
+            axes_2d: HashMap::new(),
+        }
+    }
+    pub fn load(path: &Path) -> anyhow::Result<Self> {
+        let bindings = std::fs::read_to_string(path)
+            .with_context(|| format!("Failed to read input bindings {}", path.display()))?;
+        let input_map = Self::parse(&bindings)
+            .with_context(|| format!("Failed to parse input bindings {}", path.display()))?;
+        log::info!("Loaded input bindings from {}", path.display());
+        Ok(input_map)
+    }
+    pub fn save(&self, path: &Path) -> std::io::Result<()> {
+        std::fs::write(path, self.to_bindings_string())
+    }
+    pub fn parse(bindings: &str) -> Result<Self, InputMapError> {
+        let mut input_map = Self::empty();
+        for (index, line) in bindings.lines().enumerate() {
+            let error = |message: String| InputMapError {
+                line: index + 1,
+                message,
+            };
+            let line = line.split('#').next().unwrap().trim();
+            if line.is_empty() {
+                continue;
+            }
+            let (declaration, inputs) = line.split_once('=').ok_or_else(|| {
+                error(format!("expected <kind> <name> = <inputs>, found {}", line))
+            })?;
+            let mut declaration = declaration.split_whitespace();
+            let (kind, name) = match (declaration.next(), declaration.next(), declaration.next()) {
+                (Some(kind), Some(name), None) => (kind, name),
+                _ => return Err(error("expected <kind> <name> before =".to_owned())),
+            };
+            let inputs = inputs.split(',').map(str::trim);
+            match kind {
+                "action" => {
+                    for input in inputs {
+                        let binding = parse_button(input).map_err(error)?;
+                        input_map.bind_action(name, binding);
+                    }
+                }
+                "axis" => {
+                    for input in inputs {
+                        let binding = parse_axis(input).map_err(error)?;
+                        input_map.bind_axis(name, binding);
+                    }
+                }
+                "axis2d" => {
+                    let axes: Vec<&str> = inputs.collect();
+                    match axes.as_slice() {
+                        [x, y] => {
+                            input_map.bind_axis_2d(name, x, y);
+                        }
+                        _ => return Err(error(format!("axis2d {} needs an x and a y axis", name))),
+                    }
+                }
+                _ => return Err(error(format!("unknown binding kind {}", kind))),
+            }
+        }
+        Ok(input_map)
+    }
+    // The bindings in the format parse reads, sorted by name so saved files diff well
+    pub fn to_bindings_string(&self) -> String {
+        let mut bindings = String::new();
+        let mut actions: Vec<_> = self.actions.iter().collect();
+        actions.sort_by(|a, b| a.0.cmp(b.0));
+        for (name, buttons) in actions {
+            let buttons: Vec<String> = buttons.iter().map(button_name).collect();
+            let _ = writeln!(bindings, "action {} = {}", name, buttons.join(", "));
+        }
+        let mut axes: Vec<_> = self.axes.iter().collect();
+        axes.sort_by(|a, b| a.0.cmp(b.0));
+        for (name, axis_bindings) in axes {
+            let axis_bindings: Vec<String> = axis_bindings.iter().map(axis_name).collect();
+            let _ = writeln!(bindings, "axis {} = {}", name, axis_bindings.join(", "));
+        }
+        let mut axes_2d: Vec<_> = self.axes_2d.iter().collect();
+        axes_2d.sort_by(|a, b| a.0.cmp(b.0));
+        for (name, (x, y)) in axes_2d {
+            let _ = writeln!(bindings, "axis2d {} = {}, {}", name, x, y);
+        }
+        bindings
+    }
+
+    pub fn bind_action(&mut self, name: &str, binding: ButtonBinding) -> &mut Self {
+        let bindings = self.actions.entry(name.to_owned()).or_default();
+        if !bindings.contains(&binding) {
+            bindings.push(binding);
+        }
+        self
+    }
+    // Replaces every binding of the action, for rebinding at runtime
+    pub fn rebind_action(&mut self, name: &str, bindings: Vec<ButtonBinding>) -> &mut Self {
+        self.actions.insert(name.to_owned(), bindings);
+        self
+    }
+    pub fn unbind_action(&mut self, name: &str) -> &mut Self {
+        self.actions.remove(name);
+        self
+    }
+    pub fn bind_axis(&mut self, name: &str, binding: AxisBinding) -> &mut Self {
+        self.axes.entry(name.to_owned()).or_default().push(binding);
+        self
+    }
+    pub fn rebind_axis(&mut self, name: &str, bindings: Vec<AxisBinding>) -> &mut Self {
+        self.axes.insert(name.to_owned(), bindings);
+        self
+    }
+    pub fn unbind_axis(&mut self, name: &str) -> &mut Self {
+        self.axes.remove(name);
+        self
+    }
+    pub fn bind_axis_2d(&mut self, name: &str, x: &str, y: &str) -> &mut Self {
+        self.axes_2d
+            .insert(name.to_owned(), (x.to_owned(), y.to_owned()));
+        self
+    }
+    pub fn action_bindings(&self, name: &str) -> &[ButtonBinding] {
+        self.actions
+            .get(name)
+            .map(Vec::as_slice)
+            .unwrap_or_default()
+    }
+    pub fn axis_bindings(&self, name: &str) -> &[AxisBinding] {
+        self.axes.get(name).map(Vec::as_slice).unwrap_or_default()
+    }
+
+    pub fn pressed(&self, input: &InputConsumer, action: &str) -> bool {
+        self.action_bindings(action)
+            .iter()
+            .any(|binding| binding.pressed(input))
+    }
+    // Only true for the frame one of the action's bindings went down in
+    pub fn just_pressed(&self, input: &InputConsumer, action: &str) -> bool {
+        self.action_bindings(action)
+            .iter()
+            .any(|binding| binding.just_pressed(input))
+    }
+    pub fn just_released(&self, input: &InputConsumer, action: &str) -> bool {
+        self.action_bindings(action)
+            .iter()
+            .any(|binding| binding.just_released(input))
+    }
+    pub fn axis(&self, input: &InputConsumer, axis: &str) -> f32 {
+        self.axis_bindings(axis)
+            .iter()
+            .map(|binding| binding.value(input))
+            .sum()
+    }
+    pub fn axis_2d(&self, input: &InputConsumer, axis: &str) -> Vector2<f32> {
+        match self.axes_2d.get(axis) {
+            Some((x, y)) => Vector2::new(self.axis(input, x), self.axis(input, y)),
+            None => Vector2::zeros(),
+        }
+    }
+}
+
+impl Default for InputMap {
+    // The controls that used to be hardcoded, Escape quits and the camera flies with WASD/QE and the arrow keys.
+    // On a gamepad Start quits, the left stick moves and the right stick looks around. The camera controllers
+    // sprint with shift, mouse-look with the right button held, orbit with the left and pan with the middle one
+    fn default() -> Self {
+        let mut input_map = Self::empty();
+        input_map
+            .bind_action("quit", ButtonBinding::Key(VirtualKeyCode::Escape))
+            .bind_action("quit", ButtonBinding::Gamepad(GamepadButton::Start))
+            .bind_action("sprint", ButtonBinding::Key(VirtualKeyCode::LShift))
+            .bind_action("sprint", ButtonBinding::Gamepad(GamepadButton::LeftThumb))
+            .bind_action("mouse_look", ButtonBinding::Mouse(MouseButton::Right))
+            .bind_action("orbit_rotate", ButtonBinding::Mouse(MouseButton::Left))
+            .bind_action("orbit_pan", ButtonBinding::Mouse(MouseButton::Middle))
+            .bind_axis(
+                "move_forward",
+                AxisBinding::keys(VirtualKeyCode::W, VirtualKeyCode::S),
+            )
+            .bind_axis(
+                "move_forward",
+                AxisBinding::new(AxisInput::Gamepad(GamepadAxis::LeftStickY)),
+            )
+            .bind_axis(
+                "move_right",
+                AxisBinding::keys(VirtualKeyCode::D, VirtualKeyCode::A),
+            )
+            .bind_axis(
+                "move_right",
+                AxisBinding::new(AxisInput::Gamepad(GamepadAxis::LeftStickX)),
+            )
+            .bind_axis(
+                "move_up",
+                AxisBinding::keys(VirtualKeyCode::E, VirtualKeyCode::Q),
+            )
+            .bind_axis(
+                "look_yaw",
+                AxisBinding::keys(VirtualKeyCode::Right, VirtualKeyCode::Left),
+            )
+            .bind_axis(
+                "look_yaw",
+                AxisBinding::new(AxisInput::Gamepad(GamepadAxis::RightStickX)),
+            )
+            .bind_axis(
+                "look_pitch",
+                AxisBinding::keys(VirtualKeyCode::Up, VirtualKeyCode::Down),
+            )
+            .bind_axis(
+                "look_pitch",
+                AxisBinding::new(AxisInput::Gamepad(GamepadAxis::RightStickY)),
+            )
+            .bind_axis_2d("move", "move_right", "move_forward");
+        input_map
+    }
+}
+
+// Sends AppExit when the "quit" action is pressed
+pub struct QuitOnAction {
+    name: &'static str,
+}
+
+impl QuitOnAction {
+    pub const ACTION: &'static str = "quit";
+
+    pub fn new() -> Self {
+        Self {
+            name: "QuitOnAction",
+        }
+    }
+}
+
+impl System for QuitOnAction {
+    fn startup(&mut self, _world: &mut World) -> SystemResult {
+        Ok(())
+    }
+    fn run(&mut self, world: &mut World) -> SystemResult {
+        let quit = {
+            let input_map = world
+                .get_resource::<InputMap>()
+                .context("QuitOnAction needs the InputMap resource, add the InputPlugin")?;
+            let input_consumer = world
+                .get_resource::<InputConsumer>()
+                .context("QuitOnAction needs the InputConsumer resource, add the InputPlugin")?;
+            input_map.just_pressed(&input_consumer, Self::ACTION)
+        };
+        if quit {
+            world.send_event(AppExit);
+        }
+        Ok(())
+    }
+    fn shutdown(&mut self, _world: &mut World) -> SystemResult {
+        Ok(())
+    }
+    fn name(&self) -> &str {
+        self.name
+    }
+    fn get_run_stage(&self) -> RunStage {
+        RunStage::EarlyUpdate
+    }
+}
+
+fn parse_button(input: &str) -> Result<ButtonBinding, String> {
+    match input.split_once(':') {
+        Some(("key", name)) => key_from_name(name)
+            .map(ButtonBinding::Key)
+            .ok_or_else(|| format!("unknown key {}", name)),
+        Some(("mouse", name)) => mouse_button_from_name(name)
+            .map(ButtonBinding::Mouse)
+            .ok_or_else(|| format!("unknown mouse button {}", name)),
+        Some(("pad", name)) => GamepadButton::from_name(name)
+            .map(ButtonBinding::Gamepad)
+            .ok_or_else(|| format!("unknown gamepad button {}", name)),
+        _ => Err(format!(
+            "expected key:<name>, mouse:<name> or pad:<name>, found {}",
+            input
+        )),
+    }
+}
+
+fn parse_axis(input: &str) -> Result<AxisBinding, String> {
+    let (input, scale) = match input.split_once('*') {
+        Some((input, scale)) => (
+            input.trim(),
+            scale
+                .trim()
+                .parse()
+                .map_err(|_| format!("invalid scale {}", scale))?,
+        ),
+        None => (input, 1.0),
+    };
+    let axis_input = match input {
+        "mouse_x" => AxisInput::MouseX,
+        "mouse_y" => AxisInput::MouseY,
+        "scroll_x" => AxisInput::ScrollX,
+        "scroll_y" => AxisInput::ScrollY,
+        _ if input.starts_with("pad:") && !input.contains('/') => {
+            let name = &input["pad:".len()..];
+            AxisInput::Gamepad(
+                GamepadAxis::from_name(name)
+                    .ok_or_else(|| format!("unknown gamepad axis {}", name))?,
+            )
+        }
+        _ => match input.split_once('/') {
+            Some((positive, negative)) => AxisInput::Buttons {
+                positive: parse_button(positive.trim())?,
+                negative: parse_button(negative.trim())?,
+            },
+            None => {
+                return Err(format!(
+                    "expected <positive>/<negative>, a mouse axis or a gamepad axis, found {}",
+                    input
+                ))
+            }
+        },
+    };
+    Ok(AxisBinding::new(axis_input).with_scale(scale))
+}
+
+fn button_name(button: &ButtonBinding) -> String {
+    match button {
+        ButtonBinding::Key(key_code) => format!("key:{:?}", key_code),
+        ButtonBinding::Mouse(button) => format!("mouse:{}", mouse_button_name(*button)),
+        ButtonBinding::Gamepad(button) => format!("pad:{:?}", button),
+    }
+}
+
+fn axis_name(axis: &AxisBinding) -> String {
+    let input = match axis.input {
+        AxisInput::Buttons { positive, negative } => {
+            format!("{}/{}", button_name(&positive), button_name(&negative))
+        }
+        AxisInput::MouseX => "mouse_x".to_owned(),
+        AxisInput::MouseY => "mouse_y".to_owned(),
+        AxisInput::ScrollX => "scroll_x".to_owned(),
+        AxisInput::ScrollY => "scroll_y".to_owned(),
+        AxisInput::Gamepad(axis) => format!("pad:{:?}", axis),
+    };
+    if axis.scale == 1.0 {
+        input
+    } else {
+        format!("{}*{}", input, axis.scale)
+    }
+}
+
+// Left, Right, Middle or the number of any other button
+pub(crate) fn mouse_button_from_name(name: &str) -> Option<MouseButton> {
+    match name {
+        "Left" => Some(MouseButton::Left),
+        "Right" => Some(MouseButton::Right),
+        "Middle" => Some(MouseButton::Middle),
+        _ => name.parse().ok().map(MouseButton::Other),
+    }
+}
+
+pub(crate) fn mouse_button_name(button: MouseButton) -> String {
+    match button {
+        MouseButton::Other(button) => button.to_string(),
+        button => format!("{:?}", button),
+    }
+}
+
+// VirtualKeyCode has no FromStr, names are the variant names which is also what its Debug prints
+macro_rules! key_names {
+    ($($key:ident),* $(,)?) => {
+        pub fn key_from_name(name: &str) -> Option<VirtualKeyCode> {
+            match name {
+                $(stringify!($key) => Some(VirtualKeyCode::$key),)*
+                _ => None,
+            }
+        }
+    };
+}
+
+key_names!(
+    Key1,
+    Key2,
+    Key3,
+    Key4,
+    Key5,
+    Key6,
+    Key7,
+    Key8,
+    Key9,
+    Key0,
+    A,
+    B,
+    C,
+    D,
+    E,
+    F,
+    G,
+    H,
+    I,
+    J,
+    K,
+    L,
+    M,
+    N,
+    O,
+    P,
+    Q,
+    R,
+    S,
+    T,
+    U,
+    V,
+    W,
+    X,
+    Y,
+    Z,
+    Escape,
+    F1,
+    F2,
+    F3,
+    F4,
+    F5,
+    F6,
+    F7,
+    F8,
+    F9,
+    F10,
+    F11,
+    F12,
+    F13,
+    F14,
+    F15,
+    F16,
+    F17,
+    F18,
+    F19,
+    F20,
+    F21,
+    F22,
+    F23,
+    F24,
+    Snapshot,
+    Scroll,
+    Pause,
+    Insert,
+    Home,
+    Delete,
+    End,
+    PageDown,
+    PageUp,
+    Left,
+    Up,
+    Right,
+    Down,
+    Back,
+    Return,
+    Space,
+    Compose,
+    Caret,
+    Numlock,
+    Numpad0,
+    Numpad1,
+    Numpad2,
+    Numpad3,
+    Numpad4,
+    Numpad5,
+    Numpad6,
+    Numpad7,
+    Numpad8,
+    Numpad9,
+    NumpadAdd,
+    NumpadDivide,
+    NumpadDecimal,
+    NumpadComma,
+    NumpadEnter,
+    NumpadEquals,
+    NumpadMultiply,
+    NumpadSubtract,
+    AbntC1,
+    AbntC2,
+    Apostrophe,
+    Apps,
+    Asterisk,
+    At,
+    Ax,
+    Backslash,
+    Calculator,
+    Capital,
+    Colon,
+    Comma,
+    Convert,
+    Equals,
+    Grave,
+    Kana,
+    Kanji,
+    LAlt,
+    LBracket,
+    LControl,
+    LShift,
+    LWin,
+    Mail,
+    MediaSelect,
+    MediaStop,
+    Minus,
+    Mute,
+    MyComputer,
+    NavigateForward,
+    NavigateBackward,
+    NextTrack,
+    NoConvert,
+    OEM102,
+    Period,
+    PlayPause,
+    Plus,
+    Power,
+    PrevTrack,
+    RAlt,
+    RBracket,
+    RControl,
+    RShift,
+    RWin,
+    Semicolon,
+    Slash,
+    Sleep,
+    Stop,
+    Sysrq,
+    Tab,
+    Underline,
+    Unlabeled,
+    VolumeDown,
+    VolumeUp,
+    Wake,
+    WebBack,
+    WebFavorites,
+    WebForward,
+    WebHome,
+    WebRefresh,
+    WebSearch,
+    WebStop,
+    Yen,
+    Copy,
+    Paste,
+    Cut,
+);
@@ -1,11 +1,11 @@
-use crate::adel_ecs::{System, World};
-use crate::adel_input::InputConsumer;
-use std::collections::HashSet;
-use winit::event::VirtualKeyCode;
+use crate::adel_ecs::{RunStage, System, SystemResult, With, Without, World};
+use crate::adel_input::{InputConsumer, InputMap};
+use anyhow::Context;
 use winit::window::Window;
 
-use crate::adel_camera::Camera;
+use crate::adel_camera::{Camera, FlyCamera, OrbitCamera};
 use crate::adel_renderer::definitions::TransformComponent;
+use crate::adel_time::Time;
 // This class will be a struct that contains the current input variables
 // Which keys and which state shall be contained in this class
 // Other Classes need to reference this class in order to update accordingly
@@ -28,115 +28,123 @@ impl KeyboardHandler {
 }
 
 impl System for KeyboardHandler {
-    fn startup(&mut self, world: &mut World) {
-        let window = world.get_resource::<Window>().unwrap();
-        let mut camera = world.get_resource_mut::<Camera>().unwrap();
-
-        let mut transform_ref = world.borrow_component_mut::<TransformComponent>().unwrap();
-        let input_ref = world.borrow_component::<KeyboardComponent>().unwrap();
+    fn startup(&mut self, world: &mut World) -> SystemResult {
+        let window = world
+            .get_resource::<Window>()
+            .context("KeyboardHandler needs the Window resource, add the WindowPlugin")?;
+        let mut camera = world
+            .get_resource_mut::<Camera>()
+            .context("KeyboardHandler needs the Camera resource")?;
+
+        let mut camera_query = world
+            .query_filtered::<&TransformComponent, With<KeyboardComponent>>()
+            .context("KeyboardHandler failed to query the camera transform")?;
 
         let dims = window.inner_size();
         let aspect_ratio = dims.width as f32 / dims.height as f32;
         camera.set_perspective_projection((50.0f32).to_radians(), aspect_ratio, 0.1, 100.0);
 
-        for i in input_ref.iter().enumerate() {
-            if let Some(_input_entity) = i.1 {
-                if let Some(camera_transform) = &mut transform_ref[i.0] {
-                    camera.set_view_yxz(camera_transform.translation, camera_transform.rotation);
-                    /*camera.set_view_target(
-                        camera_transform.translation,
-                        nalgebra::Vector3::<f32>::new(0.0, 0.0, 0.0),
-                        Some(nalgebra::Vector3::<f32>::new(0.0, 0.0, 1.0)),
-                    );*/
-                }
-            }
+        for camera_transform in camera_query.iter() {
+            camera.set_view_yxz(camera_transform.translation, camera_transform.rotation);
+            /*camera.set_view_target(
+                camera_transform.translation,
+                nalgebra::Vector3::<f32>::new(0.0, 0.0, 0.0),
+                Some(nalgebra::Vector3::<f32>::new(0.0, 0.0, 1.0)),
+            );*/
         }
-    }
-
-    fn run(&mut self, world: &mut World) {
-        let input_consumer = world.get_resource::<InputConsumer>().unwrap();
+        Ok(())
+    }
+
+    fn run(&mut self, world: &mut World) -> SystemResult {
+        let (movement, look) = {
+            let input_map = world
+                .get_resource::<InputMap>()
+                .context("KeyboardHandler needs the InputMap resource, add the InputPlugin")?;
+            let input_consumer = world
+                .get_resource::<InputConsumer>()
+                .context("KeyboardHandler needs the InputConsumer resource, add the InputPlugin")?;
+            let axis = |name| input_map.axis(&input_consumer, name);
+            (
+                Vector3::new(axis("move_right"), axis("move_up"), axis("move_forward")),
+                Vector3::new(axis("look_pitch"), axis("look_yaw"), 0.0),
+            )
+        };
         // No input, don't spend anymore time here
-        if input_consumer.pressed.is_empty() {
-            return;
+        if movement == Vector3::zeros() && look == Vector3::zeros() {
+            return Ok(());
         }
 
-        let input_ref = world.borrow_component::<KeyboardComponent>().unwrap();
-        let mut transform_ref = world.borrow_component_mut::<TransformComponent>().unwrap();
-        let mut camera = world.get_resource_mut::<Camera>().unwrap();
-
-        for i in input_ref.iter().enumerate() {
-            // _input_entity is used to track that this entity at this position in the Component Array exists
-            if let Some(_input_entity) = i.1 {
-                if let Some(camera_transform) = &mut transform_ref[i.0] {
-                    move_in_plane_xz(&input_consumer.pressed, world.get_dt(), camera_transform);
-                    camera.set_view_yxz(camera_transform.translation, camera_transform.rotation);
-                    /*camera.set_view_target(
-                        camera_transform.translation,
-                        nalgebra::Vector3::<f32>::new(0.0, 0.0, 0.0),
-                        Some(nalgebra::Vector3::<f32>::new(0.0, 0.0, 1.0)),
-                    );*/
-                }
-            }
+        // Only entities with a KeyboardComponent react to the keyboard, the ones with a camera controller are moved
+        // by the controller instead
+        let mut camera_query = world
+            .query_filtered::<&mut TransformComponent, (
+                With<KeyboardComponent>,
+                Without<FlyCamera>,
+                Without<OrbitCamera>,
+            )>()
+            .context("KeyboardHandler failed to query the camera transform")?;
+        let mut camera = world
+            .get_resource_mut::<Camera>()
+            .context("KeyboardHandler needs the Camera resource")?;
+        // Once per frame on the real frame time, like the camera controllers. Moving the camera in FixedUpdate made
+        // it judder since the view was set from the uninterpolated transform, and pausing Time froze it
+        let dt = world
+            .get_resource::<Time>()
+            .context("KeyboardHandler needs the Time resource, add the CorePlugin")?
+            .raw_delta();
+
+        for camera_transform in camera_query.iter() {
+            move_in_plane_xz(movement, look, dt, camera_transform);
+            camera.set_view_yxz(camera_transform.translation, camera_transform.rotation);
+            /*camera.set_view_target(
+                camera_transform.translation,
+                nalgebra::Vector3::<f32>::new(0.0, 0.0, 0.0),
+                Some(nalgebra::Vector3::<f32>::new(0.0, 0.0, 1.0)),
+            );*/
         }
+        Ok(())
     }
 
-    fn shutdown(&mut self, _world: &mut World) {}
+    fn shutdown(&mut self, _world: &mut World) -> SystemResult {
+        Ok(())
+    }
     fn name(&self) -> &str {
         self.name
     }
+    fn get_run_stage(&self) -> RunStage {
+        RunStage::Update
+    }
 }
 
 static LOOK_SPEED: f32 = 1.5;
 static MOVE_SPEED: f32 = 3.0;
 
-use nalgebra::Vector3;
+use nalgebra::{Vector2, Vector3};
 #[allow(dead_code)]
-fn move_2d_object(keys: &HashSet<VirtualKeyCode>, dt: f32, transform: &mut TransformComponent) {
-    let mut move_dir = Vector3::default();
-    if keys.contains(&VirtualKeyCode::D) {
-        move_dir.x += 1.0;
-    }
-    if keys.contains(&VirtualKeyCode::A) {
-        move_dir.x -= 1.0;
-    }
-    if keys.contains(&VirtualKeyCode::W) {
-        move_dir.y += 1.0;
-    }
-    if keys.contains(&VirtualKeyCode::S) {
-        move_dir.y -= 1.0;
-    }
+fn move_2d_object(movement: Vector2<f32>, dt: f32, transform: &mut TransformComponent) {
+    let move_dir = clamp_length(Vector3::new(movement.x, movement.y, 0.0));
+    transform.translation += MOVE_SPEED * dt * move_dir;
+}
 
-    if Vector3::dot(&move_dir, &move_dir) > f32::EPSILON {
-        transform.translation += MOVE_SPEED * dt * move_dir.normalize();
+// Button axes held together add up past 1, analog ones can stay below it
+fn clamp_length(direction: Vector3<f32>) -> Vector3<f32> {
+    if Vector3::dot(&direction, &direction) > 1.0 {
+        direction.normalize()
+    } else {
+        direction
     }
 }
 
 // TODO: Alter Camera Movement script for more control including rotation
+// movement is (right, up, forward) and look is (pitch, yaw, roll), straight from the InputMap axes. Both are
+// rates scaled by dt, mouse deltas don't belong on them
 fn move_in_plane_xz(
-    keys: &HashSet<VirtualKeyCode>,
+    movement: Vector3<f32>,
+    look: Vector3<f32>,
     dt: f32,
     camera_transform: &mut TransformComponent,
 ) {
-    let mut rotate = Vector3::new(0.0, 0.0, 0.0);
-    // Look Right
-    if keys.contains(&VirtualKeyCode::Right) {
-        rotate.y += 1.0;
-    }
-    // Look Left
-    if keys.contains(&VirtualKeyCode::Left) {
-        rotate.y -= 1.0;
-    }
-    // Look Up
-    if keys.contains(&VirtualKeyCode::Up) {
-        rotate.x += 1.0;
-    }
-    // Look Down
-    if keys.contains(&VirtualKeyCode::Down) {
-        rotate.x -= 1.0;
-    }
-    if Vector3::dot(&rotate, &rotate) > f32::EPSILON {
-        camera_transform.rotation += LOOK_SPEED * dt * rotate.normalize();
-    }
+    camera_transform.rotation += LOOK_SPEED * dt * clamp_length(look);
 
     // This is kinda dumb, look into making it more elegant
     camera_transform.rotation.x = camera_transform.rotation.x.clamp(-1.5, 1.5);
@@ -146,33 +154,6 @@ fn move_in_plane_xz(
     let forward_dir = Vector3::new(yaw.sin(), 0.0, yaw.cos());
     let right_dir = Vector3::new(forward_dir.z, 0.0, -forward_dir.x);
     let up_dir = Vector3::new(0.0, -1.0, 0.0);
-    let mut move_dir = Vector3::new(0.0, 0.0, 0.0);
-
-    // Move forward
-    if keys.contains(&VirtualKeyCode::W) {
-        move_dir += forward_dir;
-    }
-    // Move Back
-    if keys.contains(&VirtualKeyCode::S) {
-        move_dir -= forward_dir;
-    }
-    // Move Right
-    if keys.contains(&VirtualKeyCode::D) {
-        move_dir += right_dir;
-    }
-    // Move Left
-    if keys.contains(&VirtualKeyCode::A) {
-        move_dir -= right_dir;
-    }
-    // Move Up
-    if keys.contains(&VirtualKeyCode::E) {
-        move_dir += up_dir;
-    }
-    // Move Down
-    if keys.contains(&VirtualKeyCode::Q) {
-        move_dir -= up_dir;
-    }
-    if Vector3::dot(&move_dir, &move_dir) > f32::EPSILON {
-        camera_transform.translation += MOVE_SPEED * dt * move_dir.normalize();
-    }
+    let move_dir = forward_dir * movement.z + right_dir * movement.x + up_dir * movement.y;
+    camera_transform.translation += MOVE_SPEED * dt * clamp_length(move_dir);
 }
@@ -1,5 +1,15 @@
+mod gamepad;
 mod input_consumer;
+mod input_map;
 mod keyboard_movement;
+mod plugin;
+mod recording;
+mod text_input;
 
+pub use gamepad::*;
 pub use input_consumer::*;
+pub use input_map::*;
 pub use keyboard_movement::*;
+pub use plugin::*;
+pub use recording::*;
+pub use text_input::*;
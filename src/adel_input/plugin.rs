@@ -0,0 +1,56 @@
+use crate::adel_app::{AppBuilder, Plugin};
+use crate::adel_camera::{Camera, FlyCameraController, OrbitCameraController};
+use crate::adel_ecs::IntoSystemDescriptor;
+use crate::adel_input::{
+    not_typing, GamepadEvent, InputConsumer, InputMap, InputSourceSystem, InputSources,
+    KeyboardHandler, QuitOnAction, TextInput,
+};
+use winit::window::Window;
+
+// Keyboard and mouse state captured from the window events, gamepads from the InputSources, typed text, and the
+// InputMap naming it
+pub struct InputPlugin;
+
+impl Plugin for InputPlugin {
+    fn build(&self, app: &mut AppBuilder) {
+        let mut input_consumer = InputConsumer::new();
+        // Not every platform sends a resize when the window is created, headless there is no window at all
+        if app.world().contains_resource::<Window>() {
+            let window = app.world().get_resource::<Window>().unwrap();
+            input_consumer.set_window_size(window.inner_size());
+        }
+        app.insert_resource(input_consumer);
+        // Bindings can be loaded into the World before the Application is built, see InputMap::load
+        if !app.world().contains_resource::<InputMap>() {
+            app.insert_resource(InputMap::default());
+        }
+        // No gamepad backend is built in, add one to the InputSources to get controllers
+        if !app.world().contains_resource::<InputSources>() {
+            app.insert_resource(InputSources::new());
+        }
+        // A paste handler may already have been set
+        if !app.world().contains_resource::<TextInput>() {
+            app.insert_resource(TextInput::new());
+        }
+        app.add_event::<GamepadEvent>();
+        app.add_system(InputSourceSystem::new().before("QuitOnAction"));
+        // Escape in a text field is for the field to handle
+        app.add_system(QuitOnAction::new().run_if(not_typing));
+    }
+}
+
+// Flies the entity with the KeyboardComponent around with the keyboard and points the Camera through it, or lets
+// its FlyCamera/OrbitCamera controller do it
+pub struct KeyboardCameraPlugin;
+
+impl Plugin for KeyboardCameraPlugin {
+    fn build(&self, app: &mut AppBuilder) {
+        if !app.world().contains_resource::<Camera>() {
+            app.insert_resource(Camera::new());
+        }
+        app.add_system(KeyboardHandler::new().run_if(not_typing));
+        // The fly camera checks not_typing itself, it has to let go of the cursor
+        app.add_system(FlyCameraController::new());
+        app.add_system(OrbitCameraController::new().run_if(not_typing));
+    }
+}
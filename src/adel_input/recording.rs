@@ -0,0 +1,325 @@
+use crate::adel_app::AppExit;
+use crate::adel_ecs::{RunStage, System, SystemResult, World};
+use crate::adel_input::InputConsumer;
+use crate::adel_time::Time;
+use anyhow::Context;
+use std::fmt;
+use std::fmt::Write as _;
+use std::path::{Path, PathBuf};
+
+// First line of every recording, bumped whenever the format changes
+const RECORDING_HEADER: &str = "adel_input_recording 2";
+
+// What the InputConsumer held during one frame and how long the frame took
+#[derive(Debug, Clone)]
+pub struct InputFrame {
+    pub dt: f32,
+    pub input: InputConsumer,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct InputRecordingError {
+    pub line: usize,
+    pub message: String,
+}
+
+impl fmt::Display for InputRecordingError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "line {}: {}", self.line, self.message)
+    }
+}
+
+impl std::error::Error for InputRecordingError {}
+
+// Every frame's input, made by an InputRecorder and fed back by an InputPlayback to reproduce a run. Recordings
+// are text, a header line then per frame a "frame <dt>" line followed by the InputConsumer's non-empty fields:
+//   adel_input_recording 2
+//   frame 0.016666668
+//   keys W
+//   keys_down W
+//   window 800 600
+//   motion 3 -1
+//   pad 0 0.15 "Xbox Controller #2"
+// Anything after a # outside of quotes is a comment. Gamepad names are the only text, they're written in double
+// quotes, quotes, backslashes and line breaks inside them are escaped as \", \\ and \n
+#[derive(Debug, Clone, Default)]
+pub struct InputRecording {
+    frames: Vec<InputFrame>,
+}
+
+impl InputRecording {
+    pub fn new() -> Self {
+        Self::default()
+    }
+    pub fn load(path: &Path) -> anyhow::Result<Self> {
+        let recording = std::fs::read_to_string(path)
+            .with_context(|| format!("Failed to read input recording {}", path.display()))?;
+        let recording = Self::parse(&recording)
+            .with_context(|| format!("Failed to parse input recording {}", path.display()))?;
+        log::info!(
+            "Loaded {} frames of input from {}",
+            recording.len(),
+            path.display()
+        );
+        Ok(recording)
+    }
+    pub fn save(&self, path: &Path) -> std::io::Result<()> {
+        std::fs::write(path, self.to_recording_string())
+    }
+    pub fn parse(recording: &str) -> Result<Self, InputRecordingError> {
+        let mut lines = Vec::new();
+        for (index, text) in recording.lines().enumerate() {
+            let values = split_recorded_line(text).map_err(|message| InputRecordingError {
+                line: index + 1,
+                message,
+            })?;
+            if !values.is_empty() {
+                lines.push((index + 1, values));
+            }
+        }
+        let mut lines = lines.into_iter();
+        match lines.next() {
+            Some((_, values)) if values.join(" ") == RECORDING_HEADER => {}
+            other => {
+                return Err(InputRecordingError {
+                    line: other.map_or(1, |(line, _)| line),
+                    message: format!("expected {} first", RECORDING_HEADER),
+                })
+            }
+        }
+        let mut frames = Vec::new();
+        for (line, values) in lines {
+            let error = |message: String| InputRecordingError { line, message };
+            let field = values[0].as_str();
+            let values: Vec<&str> = values[1..].iter().map(String::as_str).collect();
+            if field == "frame" {
+                let dt = match values.as_slice() {
+                    [dt] => dt
+                        .parse()
+                        .map_err(|_| error(format!("invalid frame time {}", dt)))?,
+                    _ => return Err(error("expected frame <dt>".to_owned())),
+                };
+                frames.push(InputFrame {
+                    dt,
+                    input: InputConsumer::new(),
+                });
+                continue;
+            }
+            frames
+                .last_mut()
+                .ok_or_else(|| error(format!("{} before the first frame", field)))?
+                .input
+                .read_recorded(field, &values)
+                .map_err(error)?;
+        }
+        Ok(Self { frames })
+    }
+    pub fn to_recording_string(&self) -> String {
+        let mut recording = String::new();
+        let _ = writeln!(recording, "{}", RECORDING_HEADER);
+        for frame in self.frames.iter() {
+            let _ = writeln!(recording, "frame {}", frame.dt);
+            frame.input.write_recorded(&mut recording);
+        }
+        recording
+    }
+
+    pub fn push(&mut self, dt: f32, input: &InputConsumer) {
+        self.frames.push(InputFrame {
+            dt,
+            input: input.clone(),
+        });
+    }
+    pub fn frames(&self) -> &[InputFrame] {
+        &self.frames
+    }
+    pub fn len(&self) -> usize {
+        self.frames.len()
+    }
+    pub fn is_empty(&self) -> bool {
+        self.frames.is_empty()
+    }
+}
+
+// Text written so split_recorded_line gives it back as a single value, spaces and #s included
+pub(crate) fn quote_recorded(text: &str) -> String {
+    let mut quoted = String::with_capacity(text.len() + 2);
+    quoted.push('"');
+    for character in text.chars() {
+        match character {
+            '"' | '\\' => {
+                quoted.push('\\');
+                quoted.push(character);
+            }
+            '\n' => quoted.push_str("\\n"),
+            '\r' => quoted.push_str("\\r"),
+            _ => quoted.push(character),
+        }
+    }
+    quoted.push('"');
+    quoted
+}
+
+// Splits a line of a recording on whitespace, dropping the comment at the end. A value in double quotes is kept
+// whole with its escapes undone
+fn split_recorded_line(line: &str) -> Result<Vec<String>, String> {
+    let mut values = Vec::new();
+    let mut characters = line.chars().peekable();
+    while let Some(&character) = characters.peek() {
+        if character.is_whitespace() {
+            characters.next();
+        } else if character == '#' {
+            break;
+        } else if character == '"' {
+            characters.next();
+            let mut value = String::new();
+            loop {
+                match characters.next() {
+                    Some('"') => break,
+                    Some('\\') => match characters.next() {
+                        Some('n') => value.push('\n'),
+                        Some('r') => value.push('\r'),
+                        Some(escaped @ ('"' | '\\')) => value.push(escaped),
+                        Some(escaped) => return Err(format!("unknown escape \\{}", escaped)),
+                        None => return Err("unterminated quote".to_owned()),
+                    },
+                    Some(character) => value.push(character),
+                    None => return Err("unterminated quote".to_owned()),
+                }
+            }
+            values.push(value);
+        } else {
+            let mut value = String::new();
+            while let Some(&character) = characters.peek() {
+                if character.is_whitespace() || character == '#' || character == '"' {
+                    break;
+                }
+                value.push(character);
+                characters.next();
+            }
+            values.push(value);
+        }
+    }
+    Ok(values)
+}
+
+// Records the InputConsumer and the real frame time every frame, the recording is written to path on shutdown.
+// Runs in LateUpdate, by then the InputConsumer holds everything the frame's systems saw
+pub struct InputRecorder {
+    name: &'static str,
+    path: PathBuf,
+    recording: InputRecording,
+}
+
+impl InputRecorder {
+    pub fn new(path: impl Into<PathBuf>) -> Self {
+        Self {
+            name: "InputRecorder",
+            path: path.into(),
+            recording: InputRecording::new(),
+        }
+    }
+    pub fn recording(&self) -> &InputRecording {
+        &self.recording
+    }
+}
+
+impl System for InputRecorder {
+    fn startup(&mut self, _world: &mut World) -> SystemResult {
+        log::info!("Recording input to {}", self.path.display());
+        Ok(())
+    }
+    fn run(&mut self, world: &mut World) -> SystemResult {
+        let dt = world
+            .get_resource::<Time>()
+            .context("InputRecorder needs the Time resource, add the CorePlugin")?
+            .raw_delta();
+        let input_consumer = world
+            .get_resource::<InputConsumer>()
+            .context("InputRecorder needs the InputConsumer resource, add the InputPlugin")?;
+        self.recording.push(dt, &input_consumer);
+        Ok(())
+    }
+    fn shutdown(&mut self, _world: &mut World) -> SystemResult {
+        self.recording
+            .save(&self.path)
+            .with_context(|| format!("Failed to write input recording {}", self.path.display()))?;
+        log::info!(
+            "Wrote {} frames of input to {}",
+            self.recording.len(),
+            self.path.display()
+        );
+        Ok(())
+    }
+    fn name(&self) -> &str {
+        self.name
+    }
+    fn get_run_stage(&self) -> RunStage {
+        RunStage::LateUpdate
+    }
+}
+
+// Resource that replays an InputRecording. While it's playing the Application replaces the InputConsumer with
+// the recorded one at the start of every frame and uses the recorded frame time, window input and InputSources
+// are ignored. Once the last frame has played AppExit is sent, unless exit_when_done is turned off in which case
+// live input takes over again
+#[derive(Debug, Clone)]
+pub struct InputPlayback {
+    recording: InputRecording,
+    next_frame: usize,
+    // Stays set through the frame the last recorded frame played in
+    playing: bool,
+    exit_when_done: bool,
+}
+
+impl InputPlayback {
+    pub fn new(recording: InputRecording) -> Self {
+        Self {
+            playing: !recording.is_empty(),
+            recording,
+            next_frame: 0,
+            exit_when_done: true,
+        }
+    }
+    pub fn load(path: &Path) -> anyhow::Result<Self> {
+        Ok(Self::new(InputRecording::load(path)?))
+    }
+    pub fn with_exit_when_done(mut self, exit_when_done: bool) -> Self {
+        self.exit_when_done = exit_when_done;
+        self
+    }
+    pub fn is_playing(&self) -> bool {
+        self.playing
+    }
+    // Frames played so far
+    pub fn frame(&self) -> usize {
+        self.next_frame
+    }
+    pub fn recording(&self) -> &InputRecording {
+        &self.recording
+    }
+    // Called by the Application before EarlyUpdate, returns the frame time to use
+    pub(crate) fn play_frame(&mut self, world: &World) -> Option<f32> {
+        let frame = match self.recording.frames.get(self.next_frame) {
+            Some(frame) => frame,
+            None => {
+                self.playing = false;
+                return None;
+            }
+        };
+        self.next_frame += 1;
+        if world.contains_resource::<InputConsumer>() {
+            world
+                .get_resource_mut::<InputConsumer>()
+                .unwrap()
+                .clone_from(&frame.input);
+        }
+        if self.next_frame == self.recording.len() {
+            log::info!("Input playback finished after {} frames", self.next_frame);
+            if self.exit_when_done {
+                world.send_event(AppExit);
+            }
+        }
+        Some(frame.dt)
+    }
+}
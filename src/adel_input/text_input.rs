@@ -0,0 +1,274 @@
+use crate::adel_ecs::World;
+use winit::event::{ElementState, KeyboardInput, ModifiersState, VirtualKeyCode};
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum TextInputEvent {
+    Char(char),
+    Backspace,
+    Enter,
+    // Whatever the paste handler returned for Ctrl+V (Cmd+V on macOS)
+    Paste(String),
+}
+
+// Resource collecting typed text for UI fields and consoles. Text only comes in while something has taken the
+// focus, whoever owns it reads this frame's events and edits its own buffer, everything is cleared at the end of
+// the frame. While the focus is held keyboard driven systems should stay out of the way, KeyboardHandler and
+// QuitOnAction only run when not_typing, add the same run condition to any other system reading the keyboard.
+// There is no clipboard support built in, hand a paste handler to set_paste_handler to get Paste events
+#[derive(Default)]
+pub struct TextInput {
+    events: Vec<TextInputEvent>,
+    focus: Option<String>,
+    modifiers: ModifiersState,
+    paste_handler: Option<Box<dyn FnMut() -> Option<String>>>,
+}
+
+impl TextInput {
+    pub fn new() -> Self {
+        Self::default()
+    }
+    // Takes the keyboard for owner, from whoever had it before
+    pub fn take_focus(&mut self, owner: &str) {
+        if self.focus.as_deref() != Some(owner) {
+            log::info!("Text input focused by {}", owner);
+            self.focus = Some(owner.to_owned());
+        }
+    }
+    // Only releases the focus if owner is the one holding it, so a field that lost it doesn't take it from the
+    // next one
+    pub fn release_focus(&mut self, owner: &str) {
+        if self.focus.as_deref() == Some(owner) {
+            log::info!("Text input released by {}", owner);
+            self.focus = None;
+            self.events.clear();
+        }
+    }
+    pub fn focus(&self) -> Option<&str> {
+        self.focus.as_deref()
+    }
+    pub fn has_focus(&self, owner: &str) -> bool {
+        self.focus.as_deref() == Some(owner)
+    }
+    pub fn is_focused(&self) -> bool {
+        self.focus.is_some()
+    }
+    pub fn set_paste_handler(&mut self, paste_handler: impl FnMut() -> Option<String> + 'static) {
+        self.paste_handler = Some(Box::new(paste_handler));
+    }
+
+    // This frame's events in the order they were typed
+    pub fn events(&self) -> &[TextInputEvent] {
+        &self.events
+    }
+    // Characters and pasted text typed this frame, without the edits
+    pub fn text(&self) -> String {
+        let mut text = String::new();
+        for event in self.events.iter() {
+            match event {
+                TextInputEvent::Char(character) => text.push(*character),
+                TextInputEvent::Paste(pasted) => text.push_str(pasted),
+                TextInputEvent::Backspace | TextInputEvent::Enter => {}
+            }
+        }
+        text
+    }
+    // Applies this frame's events to the end of buffer, returns true if Enter was pressed
+    pub fn apply(&self, buffer: &mut String) -> bool {
+        let mut entered = false;
+        for event in self.events.iter() {
+            match event {
+                TextInputEvent::Char(character) => buffer.push(*character),
+                TextInputEvent::Paste(pasted) => buffer.push_str(pasted),
+                TextInputEvent::Backspace => {
+                    buffer.pop();
+                }
+                TextInputEvent::Enter => entered = true,
+            }
+        }
+        entered
+    }
+
+    // From WindowEvent::ReceivedCharacter. Backspace and Enter come in as control characters, macOS sends DEL
+    // for backspace, the rest of the control characters (Ctrl+V included) are dropped
+    pub fn capture_character(&mut self, character: char) {
+        if !self.is_focused() {
+            return;
+        }
+        match character {
+            '\u{8}' | '\u{7f}' => self.events.push(TextInputEvent::Backspace),
+            '\r' | '\n' => self.events.push(TextInputEvent::Enter),
+            _ if character.is_control() => {}
+            _ => self.events.push(TextInputEvent::Char(character)),
+        }
+    }
+    pub fn capture_modifiers(&mut self, modifiers: ModifiersState) {
+        self.modifiers = modifiers;
+    }
+    // Only looks for the paste shortcut, the characters themselves come through capture_character
+    pub fn capture_keyboard_input(&mut self, keyboard_input: &KeyboardInput) {
+        let paste_modifier = self.modifiers.ctrl() || self.modifiers.logo();
+        if keyboard_input.state == ElementState::Pressed
+            && keyboard_input.virtual_keycode == Some(VirtualKeyCode::V)
+            && paste_modifier
+        {
+            self.paste();
+        }
+    }
+    // Asks the paste handler for the clipboard contents
+    pub fn paste(&mut self) {
+        if !self.is_focused() {
+            return;
+        }
+        let pasted = match self.paste_handler.as_mut() {
+            Some(paste_handler) => paste_handler(),
+            None => {
+                log::warn!("Paste without a paste handler, see TextInput::set_paste_handler");
+                return;
+            }
+        };
+        if let Some(pasted) = pasted.filter(|pasted| !pasted.is_empty()) {
+            self.events.push(TextInputEvent::Paste(pasted));
+        }
+    }
+    pub(crate) fn end_frame(&mut self) {
+        self.events.clear();
+    }
+}
+
+// Run condition for systems reading the keyboard, false while a text field has the focus,
+// app.add_system(KeyboardHandler::new().run_if(not_typing))
+pub fn not_typing(world: &World) -> bool {
+    !world.contains_resource::<TextInput>()
+        || !world.get_resource::<TextInput>().unwrap().is_focused()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::cell::Cell;
+    use std::rc::Rc;
+
+    fn key(state: ElementState, key_code: VirtualKeyCode) -> KeyboardInput {
+        #[allow(deprecated)]
+        KeyboardInput {
+            scancode: 0,
+            state,
+            virtual_keycode: Some(key_code),
+            modifiers: Default::default(),
+        }
+    }
+
+    fn type_text(text_input: &mut TextInput, text: &str) {
+        for character in text.chars() {
+            text_input.capture_character(character);
+        }
+    }
+
+    #[test]
+    fn only_the_owner_releases_the_focus() {
+        let mut text_input = TextInput::new();
+        type_text(&mut text_input, "lost");
+        assert!(text_input.events().is_empty());
+
+        text_input.take_focus("name");
+        text_input.take_focus("console");
+        assert!(text_input.has_focus("console"));
+        assert!(!text_input.has_focus("name"));
+        // The field that lost the focus letting go of it doesn't take it from the console
+        text_input.release_focus("name");
+        assert_eq!(text_input.focus(), Some("console"));
+
+        type_text(&mut text_input, "ls");
+        text_input.release_focus("console");
+        assert!(!text_input.is_focused());
+        // Nothing typed before the release is left for whoever takes the focus next
+        assert!(text_input.events().is_empty());
+    }
+
+    #[test]
+    fn control_characters_are_edits_or_dropped() {
+        let mut text_input = TextInput::new();
+        text_input.take_focus("field");
+        // Backspace, macOS backspace, Escape, Ctrl+V and Enter
+        type_text(&mut text_input, "ab\u{8}c\u{7f}d\u{1b}\u{16}\r");
+        assert_eq!(
+            text_input.events(),
+            &[
+                TextInputEvent::Char('a'),
+                TextInputEvent::Char('b'),
+                TextInputEvent::Backspace,
+                TextInputEvent::Char('c'),
+                TextInputEvent::Backspace,
+                TextInputEvent::Char('d'),
+                TextInputEvent::Enter,
+            ]
+        );
+        assert_eq!(text_input.text(), "abcd");
+        let mut buffer = "> ".to_owned();
+        assert!(text_input.apply(&mut buffer));
+        assert_eq!(buffer, "> ad");
+        text_input.end_frame();
+        assert!(text_input.events().is_empty());
+    }
+
+    #[test]
+    fn paste_shortcut_asks_the_paste_handler() {
+        let mut text_input = TextInput::new();
+        text_input.take_focus("field");
+        // No handler, nothing to paste
+        text_input.capture_modifiers(ModifiersState::CTRL);
+        text_input.capture_keyboard_input(&key(ElementState::Pressed, VirtualKeyCode::V));
+        assert!(text_input.events().is_empty());
+
+        let clipboard = Rc::new(Cell::new(Some("pasted")));
+        let handler_clipboard = clipboard.clone();
+        text_input.set_paste_handler(move || handler_clipboard.get().map(str::to_owned));
+        text_input.capture_keyboard_input(&key(ElementState::Pressed, VirtualKeyCode::V));
+        // Releasing V or pressing it without the modifier doesn't paste
+        text_input.capture_keyboard_input(&key(ElementState::Released, VirtualKeyCode::V));
+        text_input.capture_modifiers(ModifiersState::empty());
+        text_input.capture_keyboard_input(&key(ElementState::Pressed, VirtualKeyCode::V));
+        // Cmd+V on macOS
+        text_input.capture_modifiers(ModifiersState::LOGO);
+        text_input.capture_keyboard_input(&key(ElementState::Pressed, VirtualKeyCode::V));
+        assert_eq!(
+            text_input.events(),
+            &[
+                TextInputEvent::Paste("pasted".to_owned()),
+                TextInputEvent::Paste("pasted".to_owned()),
+            ]
+        );
+        text_input.end_frame();
+
+        // An empty clipboard or one without text pastes nothing
+        clipboard.set(Some(""));
+        text_input.paste();
+        clipboard.set(None);
+        text_input.paste();
+        assert!(text_input.events().is_empty());
+
+        // Without the focus the handler isn't even asked
+        clipboard.set(Some("pasted"));
+        text_input.release_focus("field");
+        text_input.paste();
+        assert!(text_input.events().is_empty());
+    }
+
+    #[test]
+    fn not_typing_while_nothing_has_the_focus() {
+        let mut world = World::new();
+        assert!(not_typing(&world));
+        world.insert_resource(TextInput::new());
+        assert!(not_typing(&world));
+        world
+            .get_resource_mut::<TextInput>()
+            .unwrap()
+            .take_focus("field");
+        assert!(!not_typing(&world));
+        world
+            .get_resource_mut::<TextInput>()
+            .unwrap()
+            .release_focus("field");
+        assert!(not_typing(&world));
+    }
+}
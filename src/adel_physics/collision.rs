@@ -1,4 +1,4 @@
-use crate::adel_ecs::{System, World};
+use crate::adel_ecs::{System, SystemResult, World};
 use nalgebra::{Vector2, Vector3};
 // BoxCollider2D Component
 // Requires a location (offset from the parent/entity?)
@@ -63,9 +63,15 @@ impl Collider for BoxCollider2D {
     }
 }
 impl System for dyn Collider {
-    fn startup(&mut self, _world: &mut World) {}
-    fn run(&mut self, _world: &mut World) {}
-    fn shutdown(&mut self, _world: &mut World) {}
+    fn startup(&mut self, _world: &mut World) -> SystemResult {
+        Ok(())
+    }
+    fn run(&mut self, _world: &mut World) -> SystemResult {
+        Ok(())
+    }
+    fn shutdown(&mut self, _world: &mut World) -> SystemResult {
+        Ok(())
+    }
     fn name(&self) -> &'static str {
         self.name()
     }
@@ -1,3 +1,4 @@
+use crate::adel_ecs::{Entity, World};
 use ash::vk;
 use std::hash::{Hash, Hasher};
 
@@ -188,13 +189,13 @@ pub struct PointLightPushConstants {
     pub radius: f32,
 }
 #[repr(C)]
-#[derive(Debug)]
+#[derive(Debug, Copy, Clone)]
 pub struct PushConstantData {
     pub model_matrix: nalgebra::Matrix4<f32>,
     pub normal_matrix: nalgebra::Matrix4<f32>,
 }
 
-#[derive(Debug, Copy, Clone)]
+#[derive(Debug, Copy, Clone, PartialEq)]
 pub struct TransformComponent {
     pub translation: Vector3<f32>,
     pub scale: Vector3<f32>,
@@ -311,6 +312,64 @@ impl TransformComponent {
             Vector4::<f32>::new(0.0, 0.0, 0.0, 1.0),
         ])
     }
+    // Blends from self (alpha 0) to other (alpha 1). Rotations take the short way around so a yaw that was
+    // wrapped back to 0 doesn't spin the whole way around for a frame
+    pub fn lerp(&self, other: &TransformComponent, alpha: f32) -> TransformComponent {
+        let lerp_angle = |from: f32, to: f32| {
+            let two_pi = 2.0 * std::f32::consts::PI;
+            let delta =
+                (to - from + std::f32::consts::PI).rem_euclid(two_pi) - std::f32::consts::PI;
+            from + delta * alpha
+        };
+        TransformComponent {
+            translation: self.translation.lerp(&other.translation, alpha),
+            scale: self.scale.lerp(&other.scale, alpha),
+            rotation: Vector3::new(
+                lerp_angle(self.rotation.x, other.rotation.x),
+                lerp_angle(self.rotation.y, other.rotation.y),
+                lerp_angle(self.rotation.z, other.rotation.z),
+            ),
+        }
+    }
+}
+
+// The TransformComponent as it was before the last fixed step, rendering blends it with the current transform
+// using FixedTimestep::alpha so movement simulated at the fixed rate still looks smooth at any frame rate
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub struct PreviousTransformComponent(pub TransformComponent);
+
+// Called before every fixed step. Entities that don't have a PreviousTransformComponent yet get one
+pub fn store_previous_transforms(world: &mut World) {
+    let mut missing: Vec<(Entity, TransformComponent)> = Vec::new();
+    {
+        let mut transforms = world
+            .query::<(
+                Entity,
+                &TransformComponent,
+                Option<&mut PreviousTransformComponent>,
+            )>()
+            .expect("Failed to query transforms");
+        for (entity, transform, previous) in transforms.iter() {
+            match previous {
+                Some(previous) => previous.0 = *transform,
+                None => missing.push((entity, *transform)),
+            }
+        }
+    }
+    for (entity, transform) in missing {
+        world.add_component_to_entity(entity, PreviousTransformComponent(transform));
+    }
+}
+// The transform to draw this frame, blended with the previous fixed step when there is one
+pub fn interpolated_transform(
+    transform: &TransformComponent,
+    previous: Option<&PreviousTransformComponent>,
+    alpha: f32,
+) -> TransformComponent {
+    match previous {
+        Some(previous) => previous.0.lerp(transform, alpha),
+        None => *transform,
+    }
 }
 pub fn create_push_constant_data(
     model_matrix: Matrix4<f32>,
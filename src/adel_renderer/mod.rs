@@ -1,3 +1,4 @@
+mod plugin;
 mod point_light_renderer;
 mod renderer;
 mod simple_renderer;
@@ -5,4 +6,6 @@ pub mod utility;
 
 pub mod definitions;
 pub use definitions::*;
+pub use plugin::*;
+pub use point_light_renderer::PointLightOrbit;
 pub use renderer::*;
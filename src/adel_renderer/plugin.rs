@@ -0,0 +1,74 @@
+use crate::adel_app::{AppBuilder, Plugin};
+use crate::adel_camera::Camera;
+use crate::adel_ecs::{ErrorPolicy, IntoSystemDescriptor, RunStage, System, SystemResult, World};
+use crate::adel_renderer::{PointLightOrbit, RendererAsh, NAME};
+use anyhow::Context;
+use winit::window::Window;
+
+// Vulkan renderer drawing every ModelComponent and point light, needs the WindowPlugin added before it
+pub struct RendererPlugin;
+
+impl Plugin for RendererPlugin {
+    fn build(&self, app: &mut AppBuilder) {
+        // The renderer reads the view and projection from the Camera every frame
+        if !app.world().contains_resource::<Camera>() {
+            app.insert_resource(Camera::new());
+        }
+        // A Vulkan error leaves nothing sensible to draw with, shut down cleanly instead of drawing garbage
+        app.add_system(PointLightOrbit::new())
+            .add_system(RendererSystem::new().on_error(ErrorPolicy::Shutdown));
+    }
+}
+
+// Creates the RendererAsh in startup, so a missing Window or a failed Vulkan setup is reported like any other
+// system error instead of panicking while the plugins are built
+pub struct RendererSystem {
+    renderer: Option<RendererAsh>,
+    name: &'static str,
+}
+
+impl RendererSystem {
+    pub fn new() -> Self {
+        Self {
+            renderer: None,
+            name: NAME,
+        }
+    }
+}
+
+impl Default for RendererSystem {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl System for RendererSystem {
+    fn startup(&mut self, world: &mut World) -> SystemResult {
+        let renderer = {
+            let window = world
+                .get_resource::<Window>()
+                .context("No Window resource, add the WindowPlugin before the RendererPlugin")?;
+            RendererAsh::new(&window).context("Failed to create the renderer")?
+        };
+        self.renderer.insert(renderer).startup(world)
+    }
+    fn run(&mut self, world: &mut World) -> SystemResult {
+        self.renderer
+            .as_mut()
+            .context("The renderer failed to start up")?
+            .run(world)
+    }
+    // Nothing to shut down if startup failed
+    fn shutdown(&mut self, world: &mut World) -> SystemResult {
+        match self.renderer.as_mut() {
+            Some(renderer) => renderer.shutdown(world),
+            None => Ok(()),
+        }
+    }
+    fn name(&self) -> &'static str {
+        self.name
+    }
+    fn get_run_stage(&self) -> RunStage {
+        RunStage::RedrawUpdate
+    }
+}
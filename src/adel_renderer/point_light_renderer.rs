@@ -1,3 +1,5 @@
+use crate::adel_app::FixedTimestep;
+use crate::adel_ecs::{Query, RunStage, System, SystemResult, World};
 use crate::adel_tools::as_bytes;
 use crate::renderer::UniformBufferObject;
 use crate::{
@@ -7,10 +9,9 @@ use crate::{
     },
     renderer::PointLightComponent,
 };
-use std::cell::{Ref, RefMut};
 
 use crate::adel_renderer::{vec3_to_vec4, vec4_to_vec3};
-use anyhow::Result;
+use anyhow::{Context, Result};
 use ash::vk;
 use inline_spirv::include_spirv;
 use nalgebra::Vector4;
@@ -37,16 +38,13 @@ impl PointLightRenderer {
             pipeline,
         })
     }
-    // TODO: Remove Mut from transform Component, its there for the update function
     pub fn render(
         &self,
         device: &ash::Device,
         command_buffer: vk::CommandBuffer,
         frame_index: usize,
         descriptors: &AshDescriptors,
-        point_light_entities: &Vec<usize>,
-        point_lights: &Ref<Vec<Option<PointLightComponent>>>,
-        transforms: &RefMut<Vec<Option<TransformComponent>>>,
+        point_lights: &[(PointLightComponent, TransformComponent)],
     ) -> Result<()> {
         //let device_size_offsets: [vk::DeviceSize; 1] = [0];
         let descriptor_sets_to_bind = [descriptors.global_descriptor_sets[frame_index]];
@@ -65,9 +63,7 @@ impl PointLightRenderer {
                 &descriptor_sets_to_bind,
                 &[],
             );
-            for entity in point_light_entities.iter() {
-                let point_light = point_lights[*entity].unwrap();
-                let transform = transforms[*entity].unwrap();
+            for (point_light, transform) in point_lights {
                 let push: PointLightPushConstants = PointLightPushConstants {
                     position: Vector4::new(
                         transform.translation.x,
@@ -87,45 +83,44 @@ impl PointLightRenderer {
                 );
                 device.cmd_draw(command_buffer, 6, 1, 0, 0);
             }
-            for i in point_lights.iter() {}
         }
         Ok(())
     }
-    pub fn update(
+    // Moves every light around the Y axis, frame rate independent as long as it's run at a fixed dt
+    pub fn orbit(
         dt: f32,
-        point_light_entities: &Vec<usize>,
-        point_lights: &Ref<Vec<Option<PointLightComponent>>>,
-        transform_component: &mut RefMut<Vec<Option<TransformComponent>>>,
-        ubo: &mut UniformBufferObject,
-    ) -> Result<()> {
+        point_lights: &mut Query<(&PointLightComponent, &mut TransformComponent)>,
+    ) {
         let axis = nalgebra::Unit::new_normalize(nalgebra::Vector3::new(0.0, -1.0, 0.0));
         let rotation = nalgebra::Matrix4::<f32>::identity()
             * nalgebra::Rotation3::from_axis_angle(&axis, 0.5 * dt).to_homogeneous();
+        for (_point_light, transform) in point_lights.iter() {
+            let translation = vec3_to_vec4(transform.translation);
+            transform.translation = vec4_to_vec3(rotation * translation);
+        }
+    }
+    // Copies the lights, already interpolated for this frame, into the ubo
+    pub fn update(
+        point_lights: &[(PointLightComponent, TransformComponent)],
+        ubo: &mut UniformBufferObject,
+    ) -> Result<()> {
         let mut light_index = 0;
-        for entity in point_light_entities {
-            if let Some(point_light) = point_lights[*entity] {
-                if let Some(transform) = &mut transform_component[*entity] {
-                    let translation = vec3_to_vec4(transform.translation);
-                    transform.translation = vec4_to_vec3(rotation * translation);
-
-                    // copy light to ubo
-                    ubo.point_lights[light_index].position = Vector4::new(
-                        transform.translation.x,
-                        transform.translation.y,
-                        transform.translation.z,
-                        1.0,
-                    );
-                    ubo.point_lights[light_index].color = Vector4::new(
-                        point_light.color.x,
-                        point_light.color.y,
-                        point_light.color.z,
-                        1.0,
-                    );
+        for (point_light, transform) in point_lights {
+            // copy light to ubo
+            ubo.point_lights[light_index].position = Vector4::new(
+                transform.translation.x,
+                transform.translation.y,
+                transform.translation.z,
+                1.0,
+            );
+            ubo.point_lights[light_index].color = Vector4::new(
+                point_light.color.x,
+                point_light.color.y,
+                point_light.color.z,
+                1.0,
+            );
 
-                    light_index += 1;
-                }
-            }
-            // update light position
+            light_index += 1;
         }
         ubo.num_lights = light_index as u8;
         Ok(())
@@ -217,3 +212,39 @@ impl PointLightRenderer {
         device.destroy_pipeline_layout(self.pipeline_layout, None);
     }
 }
+
+// Spins the point lights around the scene, runs in FixedUpdate so the speed doesn't depend on frame rate
+pub struct PointLightOrbit {
+    name: &'static str,
+}
+
+impl PointLightOrbit {
+    pub fn new() -> Self {
+        Self {
+            name: "PointLightOrbit",
+        }
+    }
+}
+
+impl System for PointLightOrbit {
+    fn startup(&mut self, _world: &mut World) -> SystemResult {
+        Ok(())
+    }
+    fn run(&mut self, world: &mut World) -> SystemResult {
+        let dt = world.get_resource::<FixedTimestep>().unwrap().step();
+        let mut point_lights = world
+            .query::<(&PointLightComponent, &mut TransformComponent)>()
+            .context("Failed to query point lights")?;
+        PointLightRenderer::orbit(dt, &mut point_lights);
+        Ok(())
+    }
+    fn shutdown(&mut self, _world: &mut World) -> SystemResult {
+        Ok(())
+    }
+    fn name(&self) -> &str {
+        self.name
+    }
+    fn get_run_stage(&self) -> RunStage {
+        RunStage::FixedUpdate
+    }
+}
@@ -1,4 +1,4 @@
-use anyhow::{anyhow, Result};
+use anyhow::{anyhow, Context, Result};
 use ash::vk;
 //use winit::event::{Event, VirtualKeyCode, ElementState, KeyboardInput, WindowEvent};
 //use winit::event_loop::{EventLoop, ControlFlow};
@@ -7,11 +7,15 @@ use crate::renderer::UniformBufferObject;
 #[allow(unused_imports)]
 use nalgebra::{Matrix4, Vector3};
 
-use crate::adel_ecs::{System, World};
+use crate::adel_ecs::{Added, Changed, Entity, EventReader, Events, System, SystemResult, World};
 #[warn(unused_imports)]
 use crate::adel_tools::{print_column_order_matrix_row_ordered, print_type_of};
 // TODO: Create a prelude and add these to it
-use super::definitions::{PointLightComponent, PushConstantData, TransformComponent};
+use super::definitions::{
+    create_push_constant_data, interpolated_transform, PointLightComponent,
+    PreviousTransformComponent, PushConstantData, TransformComponent,
+};
+use crate::adel_app::FixedTimestep;
 use crate::adel_camera::Camera;
 use crate::adel_renderer::{
     point_light_renderer::PointLightRenderer,
@@ -29,7 +33,8 @@ use crate::adel_renderer::{
 };
 
 use crate::adel_renderer::utility::constants::MAX_FRAMES_IN_FLIGHT;
-use std::sync::mpsc;
+use crate::adel_winit::WindowResized;
+use std::collections::HashMap;
 use winit::window::Window;
 pub const NAME: &'static str = "Renderer";
 
@@ -54,14 +59,16 @@ pub struct RendererAsh {
     is_framebuffer_resized: bool,
     window_size: (u32, u32),
     name: &'static str,
-    receiver: mpsc::Receiver<(u32, u32)>,
+    resize_reader: EventReader<WindowResized>,
 
     is_frame_started: bool,
+    model_push_constants: HashMap<Entity, PushConstantData>,
+    model_resources: ModelResourceTracker,
 }
 
 impl RendererAsh {
     //pub fn new(window: Rc<Window>) -> Result<Self> {
-    pub fn new(window: &Window, receiver: mpsc::Receiver<(u32, u32)>) -> Result<Self> {
+    pub fn new(window: &Window) -> Result<Self> {
         // init vulkan stuff
         let entry = unsafe { ash::Entry::load()? };
         let context = AshContext::new(&entry, &window)?;
@@ -102,8 +109,10 @@ impl RendererAsh {
             is_framebuffer_resized: false,
             window_size,
             name: NAME,
-            receiver,
+            resize_reader: EventReader::new(),
             is_frame_started: false,
+            model_push_constants: HashMap::new(),
+            model_resources: ModelResourceTracker::default(),
         })
     }
     // Will be worth revisiting at a later time if splitting up draw_frame is desired
@@ -352,12 +361,85 @@ impl RendererAsh {
     pub fn update_window_size(&mut self, width: u32, height: u32) {
         self.window_size = (width, height);
     }
-    fn consume_events(&mut self) {
-        if let Ok(window_size) = self.receiver.try_recv() {
-            self.update_window_size(window_size.0, window_size.1);
+    fn consume_events(&mut self, world: &World) -> Result<()> {
+        // Several resizes can land in a single frame, only the last one matters
+        let resized = match world.get_resource::<Events<WindowResized>>() {
+            Some(events) => self.resize_reader.read(&events).last().copied(),
+            None => None,
+        };
+        if let Some(window_size) = resized {
+            self.update_window_size(window_size.width, window_size.height);
             self.recreate_swapchain()
-                .expect("Failed to recreate_swapchain");
+                .context("Failed to recreate_swapchain")?;
         }
+        Ok(())
+    }
+    // Builds the GPU buffers of every ModelComponentBuilder added since the renderer last ran
+    fn build_added_models(&mut self, world: &mut World) -> Result<()> {
+        let mut models: Vec<(Entity, ModelComponent)> = Vec::new();
+        {
+            let mut builders = world
+                .query_filtered::<(Entity, &ModelComponentBuilder), Added<ModelComponentBuilder>>()
+                .context("Failed to query model builders")?;
+            for (entity, builder) in builders.iter() {
+                models.push((
+                    entity,
+                    builder
+                        .build(
+                            &self.context,
+                            &self.device,
+                            self.swapchain.single_submit_command_pool(),
+                            self.swapchain.graphics_queue,
+                        )
+                        .context("Failed to build model")?,
+                ));
+            }
+        }
+
+        for (entity, model) in models {
+            world.add_component_to_entity(entity, model);
+        }
+        Ok(())
+    }
+    // Model and normal matrices are cached per entity and only recomputed when the transform changes
+    fn invalidate_model_push_constants(&mut self, world: &World) -> Result<()> {
+        for entity in world.removed::<TransformComponent>() {
+            self.model_push_constants.remove(&entity);
+        }
+        for entity in world.removed::<ModelComponent>() {
+            self.model_push_constants.remove(&entity);
+        }
+        let mut changed = world
+            .query_filtered::<Entity, Changed<TransformComponent>>()
+            .context("Failed to query changed transforms")?;
+        for entity in changed.iter() {
+            self.model_push_constants.remove(&entity);
+        }
+        Ok(())
+    }
+    // Despawned and removed models are dropped without touching the GPU, their buffers are freed here once no
+    // live model uses them anymore, see ModelResourceTracker
+    fn free_removed_models(&mut self, world: &World) -> Result<()> {
+        let stale = {
+            let mut models = world
+                .query::<(Entity, &ModelComponent)>()
+                .context("Failed to query models")?;
+            self.model_resources
+                .update(models.iter().map(|(entity, model)| (entity, model.gpu_resources())))
+        };
+        if stale.is_empty() {
+            return Ok(());
+        }
+        // Frames still in flight may be drawing with them
+        unsafe {
+            self.device
+                .device_wait_idle()
+                .context("Failed to wait device idle before freeing models")?;
+        }
+        for resources in stale {
+            resources.destroy(&self.device);
+        }
+        Ok(())
     }
     fn destroy_swapchain_resources(&mut self) {
         unsafe {
@@ -371,35 +453,15 @@ impl RendererAsh {
 
 use crate::adel_ecs::RunStage;
 impl System for RendererAsh {
-    fn startup(&mut self, world: &mut World) {
-        let mut model_vec: Vec<Option<ModelComponent>> = Vec::new();
-
-        {
-            let model_component_builder =
-                world.borrow_component::<ModelComponentBuilder>().unwrap();
-            for mc in model_component_builder.iter() {
-                if let Some(component) = mc {
-                    // TODO: Make Component Push fuction to account for None
-                    model_vec.push(Some(
-                        component
-                            .build(
-                                &self.context,
-                                &self.device,
-                                self.swapchain.single_submit_command_pool(),
-                                self.swapchain.graphics_queue,
-                            )
-                            .expect("Failed to build model"),
-                    ));
-                } else {
-                    model_vec.push(None);
-                }
-            }
-        }
-
-        world.insert_component(model_vec);
+    fn startup(&mut self, world: &mut World) -> SystemResult {
+        self.build_added_models(world)
     }
-    fn run(&mut self, world: &mut World) {
-        self.consume_events();
+    fn run(&mut self, world: &mut World) -> SystemResult {
+        self.consume_events(world)?;
+        // Models spawned after startup still need their GPU buffers built
+        self.build_added_models(world)?;
+        self.invalidate_model_push_constants(world)?;
+        self.free_removed_models(world)?;
 
         /*{
             let point_light_component =
@@ -422,70 +484,80 @@ impl System for RendererAsh {
             .expect("Failed to update point light");
         }*/
 
-        let mut transform_component = world.borrow_component_mut::<TransformComponent>().unwrap();
-        let models = world.borrow_component::<ModelComponent>().unwrap();
-        let mut model_push_vec: Vec<(&ModelComponent, PushConstantData)> = Vec::new();
-        for i in models.iter().enumerate() {
-            if let Some(buffer) = i.1 {
-                if let Some(transform) = &transform_component[i.0] {
-                    let model_matrix = transform.mat4_less_computation();
-                    let normal_matrix = transform.normal_matrix_mat4();
-                    let push = PushConstantData {
-                        model_matrix,
-                        normal_matrix,
-                    };
-                    model_push_vec.push((buffer, push));
-                }
-            }
-        }
-
-        let point_light_component = world.borrow_component::<PointLightComponent>().unwrap();
-        let mut point_lights = Vec::new();
-        let mut point_light_entities: Vec<usize> = Vec::new();
-        for i in point_light_component.iter().enumerate() {
-            if let Some(light) = i.1 {
-                point_light_entities.push(i.0);
-                point_lights.push(light.clone());
-            }
-        }
         //log::info!("Frame begun");
-        let camera = world.get_resource::<Camera>().unwrap();
+        let camera = world
+            .get_resource::<Camera>()
+            .context("Renderer needs the Camera resource")?;
         let projection = camera.get_projection();
         let view = camera.get_view();
         let inverse_view = camera.get_inverse_view();
 
-        let num_lights = point_lights.len() as u8;
-        let mut point_lights_array: [PointLightComponent; 10] =
-            [PointLightComponent::default(); 10];
-
-        //TODO: Find a better way of doing this, every frame PointLights Array gets populated with
-        // The inital point_lights value and that gets immediately stepped on Transform Component.
-        // Perhaps I need to define a component separate than what I store that only has color
-        for i in point_lights.iter().enumerate() {
-            point_lights_array[i.0] = i.1.clone();
-        }
         let ambient_light_color = nalgebra::Vector4::<f32>::new(1.0, 1.0, 1.0, 0.02);
 
+        // Point light positions and colors are filled in by PointLightRenderer::update
         let mut ubo = UniformBufferObject {
             projection,
             view,
             inverse_view,
             ambient_light_color,
-            point_lights: point_lights_array,
-            num_lights,
+            point_lights: [PointLightComponent::default(); 10],
+            num_lights: 0,
         };
+        // Everything simulated in FixedUpdate is drawn part way between the last two fixed steps
+        let alpha = world
+            .get_resource::<FixedTimestep>()
+            .context("Renderer needs the FixedTimestep resource, add the CorePlugin")?
+            .alpha();
+        let point_lights: Vec<(PointLightComponent, TransformComponent)> = world
+            .query::<(
+                &PointLightComponent,
+                &TransformComponent,
+                Option<&PreviousTransformComponent>,
+            )>()
+            .context("Failed to query point lights")?
+            .iter()
+            .map(|(point_light, transform, previous)| {
+                (
+                    *point_light,
+                    interpolated_transform(transform, previous, alpha),
+                )
+            })
+            .collect();
+        PointLightRenderer::update(&point_lights, &mut ubo)
+            .context("Failed to update Point Lights UBO")?;
+
+        let mut models = world
+            .query::<(
+                Entity,
+                &ModelComponent,
+                &TransformComponent,
+                Option<&PreviousTransformComponent>,
+            )>()
+            .context("Failed to query models")?;
+        let mut model_push_vec: Vec<(&ModelComponent, PushConstantData)> = Vec::new();
+        for (entity, buffer, transform, previous) in models.iter() {
+            // Only transforms that didn't move in the last fixed step can use the cached matrices
+            let push = match previous {
+                Some(previous) if previous.0 != *transform => {
+                    let transform = interpolated_transform(transform, Some(previous), alpha);
+                    create_push_constant_data(
+                        transform.mat4_less_computation(),
+                        transform.normal_matrix_mat4(),
+                    )
+                }
+                _ => *self.model_push_constants.entry(entity).or_insert_with(|| {
+                    create_push_constant_data(
+                        transform.mat4_less_computation(),
+                        transform.normal_matrix_mat4(),
+                    )
+                }),
+            };
+            model_push_vec.push((buffer, push));
+        }
+
         let (wait_fence, image_index, command_buffer) =
-            self.begin_frame().expect("Failed to begin frame");
-        PointLightRenderer::update(
-            world.get_dt(),
-            &point_light_entities,
-            &point_light_component,
-            &mut transform_component,
-            &mut ubo,
-        )
-        .expect("Failed to update Point Lights UBO");
+            self.begin_frame().context("Failed to begin frame")?;
 
-        // This will work for now since the mutable reference to transformcomponent ends after the last for loop
         // IMPORTANT: Do not update global uniform buffer until AFTER the fence has been signaled
         // IMPORTANT: Setting the total number of UniformBuffers equal to MAX_FRAMES_IN_FLIGHT created a race condition. 
         // Create number of uniform buffers equal to the total number of SwapChain Images to avoid reading/writing to the same memory
@@ -495,7 +567,7 @@ impl System for RendererAsh {
             ubo,
             self.uniform_buffers_mapped[image_index as usize],
         )
-        .expect("Failed to update Uniform Buffers");
+        .context("Failed to update Uniform Buffers")?;
 
         self.begin_swapchain_render_pass(image_index, &command_buffer);
         self.simple_renderer
@@ -506,37 +578,33 @@ impl System for RendererAsh {
                 self.current_frame,
                 &self.descriptors,
             )
-            .expect("Failed to draw frame");
+            .context("Failed to draw frame")?;
         self.point_light_renderer
             .render(
                 &self.device,
                 command_buffer,
                 self.current_frame,
                 &self.descriptors,
-                &point_light_entities,
-                &point_light_component,
-                &transform_component,
+                &point_lights,
             )
-            .expect("Failed to draw frame");
+            .context("Failed to draw frame")?;
         self.end_swapchain_render_pass(&command_buffer);
         self.end_frame(image_index, wait_fence, command_buffer)
-            .expect("Failed to end frame");
+            .context("Failed to end frame")
     }
     // TODO: When Uniform buffers, Textures, and Models are abstracted to components, they need to be freed here
-    fn shutdown(&mut self, world: &mut World) {
+    fn shutdown(&mut self, world: &mut World) -> SystemResult {
+        // Picks up models added since the last frame and frees any removed since
+        self.free_removed_models(world)?;
         unsafe {
             self.device
                 .device_wait_idle()
-                .expect("ERROR: Failed to wait device idle on shutdown");
-
-            if let Some(mut models) = world.borrow_component_mut::<ModelComponent>() {
-                for model in models.iter_mut() {
-                    if let Some(i) = model {
-                        i.destroy_model_component(&self.device);
-                    }
-                }
-            }
+                .context("Failed to wait device idle on shutdown")?;
         };
+        for resources in self.model_resources.drain() {
+            resources.destroy(&self.device);
+        }
+        Ok(())
     }
     fn name(&self) -> &'static str {
         self.name
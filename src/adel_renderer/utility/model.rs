@@ -1,3 +1,4 @@
+use crate::adel_ecs::Entity;
 use crate::adel_renderer::definitions::Vertex;
 use crate::adel_renderer::utility::{
     buffer::AshBuffer, context::AshContext, descriptors::AshDescriptors,
@@ -6,7 +7,7 @@ use anyhow::Result;
 use ash::vk;
 use image::{DynamicImage, RgbaImage};
 use nalgebra::{Vector2, Vector3};
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use std::fs::File;
 use std::io::BufReader;
 use std::path::Path;
@@ -31,30 +32,96 @@ impl ModelComponent {
         ModelComponentBuilder::new()
     }
 
+    // Only for models that were never added to an entity, once the renderer has seen a model it frees the
+    // buffers itself and destroying them here as well would free them twice
     pub fn destroy_model_component(&mut self, device: &ash::Device) {
-        unsafe {
-            device.destroy_buffer(self.vertex_buffer.buffer(), None);
-            device.free_memory(self.vertex_buffer.memory(), None);
-            device.destroy_buffer(self.index_buffer.buffer(), None);
-            device.free_memory(self.index_buffer.memory(), None);
-            match self.texture_image {
-                Some(texture_image) => {
-                    device.destroy_image(self.texture_image.unwrap(), None);
-                    device.free_memory(self.texture_image_memory.unwrap(), None);
-                    device.destroy_image_view(self.texture_image_view.unwrap(), None);
-                    device.destroy_sampler(self.texture_sampler.unwrap(), None);
-                }
-                None => {}
+        self.gpu_resources().destroy(device);
+    }
+    pub fn gpu_resources(&self) -> ModelGpuResources {
+        let texture = match (
+            self.texture_image,
+            self.texture_image_memory,
+            self.texture_image_view,
+            self.texture_sampler,
+        ) {
+            (Some(image), Some(memory), Some(view), Some(sampler)) => {
+                Some((image, memory, view, sampler))
             }
+            _ => None,
+        };
+        ModelGpuResources {
+            vertex_buffer: self.vertex_buffer.buffer(),
+            vertex_memory: self.vertex_buffer.memory(),
+            index_buffer: self.index_buffer.buffer(),
+            index_memory: self.index_buffer.memory(),
+            texture,
+        }
+    }
+}
+
+// Copy of the Vulkan handles a ModelComponent owns. The renderer keeps one per entity, by the time a despawn or
+// remove_component shows up in World::removed the component itself has already been dropped
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct ModelGpuResources {
+    vertex_buffer: vk::Buffer,
+    vertex_memory: vk::DeviceMemory,
+    index_buffer: vk::Buffer,
+    index_memory: vk::DeviceMemory,
+    texture: Option<(vk::Image, vk::DeviceMemory, vk::ImageView, vk::Sampler)>,
+}
 
-            //for i in self.uniform_buffers.iter().enumerate() {
-            //    device.destroy_buffer(self.uniform_buffers[i.0], None);
-            //    device.free_memory(self.uniform_buffers_memory[i.0], None);
-            //}
+impl ModelGpuResources {
+    // The device must be done with them, wait for it to go idle first
+    pub fn destroy(&self, device: &ash::Device) {
+        unsafe {
+            device.destroy_buffer(self.vertex_buffer, None);
+            device.free_memory(self.vertex_memory, None);
+            device.destroy_buffer(self.index_buffer, None);
+            device.free_memory(self.index_memory, None);
+            if let Some((image, memory, view, sampler)) = self.texture {
+                device.destroy_image(image, None);
+                device.free_memory(memory, None);
+                device.destroy_image_view(view, None);
+                device.destroy_sampler(sampler, None);
+            }
         }
     }
 }
 
+// The renderer owns the GPU side of every ModelComponent it has drawn. Every frame it hands over the handles of
+// all the live models and gets back the ones no live model references anymore, whether their entity was
+// despawned, the component removed or replaced. A model moved to another entity or shared between entities
+// keeps its buffers for as long as one of them still has it
+#[derive(Debug, Default)]
+pub(crate) struct ModelResourceTracker {
+    resources: HashMap<Entity, ModelGpuResources>,
+}
+
+impl ModelResourceTracker {
+    pub(crate) fn update(
+        &mut self,
+        live: impl IntoIterator<Item = (Entity, ModelGpuResources)>,
+    ) -> Vec<ModelGpuResources> {
+        let previous = std::mem::take(&mut self.resources);
+        self.resources.extend(live);
+        let in_use: HashSet<ModelGpuResources> = self.resources.values().copied().collect();
+        let stale: HashSet<ModelGpuResources> = previous
+            .into_values()
+            .filter(|resources| !in_use.contains(resources))
+            .collect();
+        stale.into_iter().collect()
+    }
+    // Everything still tracked, each set of handles once, on shutdown
+    pub(crate) fn drain(&mut self) -> Vec<ModelGpuResources> {
+        let resources: HashSet<ModelGpuResources> = self
+            .resources
+            .drain()
+            .map(|(_, resources)| resources)
+            .collect();
+        resources.into_iter().collect()
+    }
+}
+
 pub struct ModelComponentBuilder {
     vertices: Option<Vec<Vertex>>,
     indices: Option<Vec<u32>>,
@@ -232,3 +299,62 @@ impl ModelComponentBuilder {
         })
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::adel_ecs::World;
+    use ash::vk::Handle;
+
+    fn resources(raw: u64) -> ModelGpuResources {
+        ModelGpuResources {
+            vertex_buffer: vk::Buffer::from_raw(raw),
+            vertex_memory: vk::DeviceMemory::from_raw(raw),
+            index_buffer: vk::Buffer::from_raw(raw + 1),
+            index_memory: vk::DeviceMemory::from_raw(raw + 1),
+            texture: None,
+        }
+    }
+
+    #[test]
+    fn moved_models_are_not_freed() {
+        let mut world = World::new();
+        let a = world.new_entity();
+        let b = world.new_entity();
+        let mut tracker = ModelResourceTracker::default();
+        assert!(tracker.update([(a, resources(10))]).is_empty());
+        // Removed from a and added to b before the renderer ran
+        assert!(tracker.update([(b, resources(10))]).is_empty());
+        assert_eq!(tracker.update([]), vec![resources(10)]);
+    }
+
+    #[test]
+    fn replaced_and_despawned_models_are_freed_once() {
+        let mut world = World::new();
+        let a = world.new_entity();
+        let b = world.new_entity();
+        let mut tracker = ModelResourceTracker::default();
+        tracker.update([(a, resources(10)), (b, resources(20))]);
+        assert_eq!(
+            tracker.update([(a, resources(30)), (b, resources(20))]),
+            vec![resources(10)]
+        );
+        assert_eq!(tracker.update([(a, resources(30))]), vec![resources(20)]);
+        assert_eq!(tracker.drain(), vec![resources(30)]);
+        assert!(tracker.update([]).is_empty());
+    }
+
+    #[test]
+    fn shared_models_live_until_the_last_entity() {
+        let mut world = World::new();
+        let a = world.new_entity();
+        let b = world.new_entity();
+        let mut tracker = ModelResourceTracker::default();
+        tracker.update([(a, resources(10)), (b, resources(10))]);
+        assert!(tracker.update([(b, resources(10))]).is_empty());
+        assert_eq!(tracker.update([]), vec![resources(10)]);
+
+        tracker.update([(a, resources(10)), (b, resources(10))]);
+        assert_eq!(tracker.drain(), vec![resources(10)]);
+    }
+}
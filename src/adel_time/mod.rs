@@ -0,0 +1,7 @@
+mod plugin;
+mod time;
+mod timer;
+
+pub use plugin::*;
+pub use time::*;
+pub use timer::*;
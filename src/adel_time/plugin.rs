@@ -0,0 +1,13 @@
+use crate::adel_app::{AppBuilder, Plugin};
+use crate::adel_time::{Time, TimerFinished, TimerSystem};
+
+// The Time resource plus everything driven by it
+pub struct TimePlugin;
+
+impl Plugin for TimePlugin {
+    fn build(&self, app: &mut AppBuilder) {
+        app.insert_resource(Time::new())
+            .add_event::<TimerFinished>()
+            .add_system(TimerSystem::new());
+    }
+}
@@ -0,0 +1,108 @@
+// Frame timing, updated by the Application at the start of every frame. Systems should use delta, which is
+// scaled by time_scale and zero while paused, so slow motion and pausing apply to everything at once. raw_delta
+// is the real wall clock frame time for things that need to keep running while paused, like menus or the
+// KeyboardHandler and camera controllers. FixedUpdate steps are taken out of the scaled delta, so anything moved
+// there stops while paused.
+#[derive(Debug, Clone)]
+pub struct Time {
+    raw_delta: f32,
+    delta: f32,
+    raw_elapsed: f64,
+    elapsed: f64,
+    frame_count: u64,
+    time_scale: f32,
+    paused: bool,
+    // Exponential moving average of raw_delta, weighted by smoothing
+    average_frame_time: f32,
+    smoothing: f32,
+}
+
+impl Time {
+    pub const DEFAULT_SMOOTHING: f32 = 0.1;
+
+    pub fn new() -> Self {
+        Self {
+            raw_delta: 0.0,
+            delta: 0.0,
+            raw_elapsed: 0.0,
+            elapsed: 0.0,
+            frame_count: 0,
+            time_scale: 1.0,
+            paused: false,
+            average_frame_time: 0.0,
+            smoothing: Self::DEFAULT_SMOOTHING,
+        }
+    }
+    // Starts a new frame that took raw_delta seconds of real time
+    pub fn update(&mut self, raw_delta: f32) {
+        self.raw_delta = raw_delta;
+        self.delta = if self.paused {
+            0.0
+        } else {
+            raw_delta * self.time_scale
+        };
+        self.raw_elapsed += raw_delta as f64;
+        self.elapsed += self.delta as f64;
+        // The first frame seeds the average, otherwise it would take dozens of frames to climb up from 0
+        self.average_frame_time = if self.frame_count == 0 {
+            raw_delta
+        } else {
+            self.average_frame_time + (raw_delta - self.average_frame_time) * self.smoothing
+        };
+        self.frame_count += 1;
+    }
+    // Scaled seconds since the last frame, 0 while paused
+    pub fn delta(&self) -> f32 {
+        self.delta
+    }
+    // Real seconds since the last frame, ignores time_scale and pausing
+    pub fn raw_delta(&self) -> f32 {
+        self.raw_delta
+    }
+    // Scaled seconds since the Application started, stops while paused
+    pub fn elapsed(&self) -> f64 {
+        self.elapsed
+    }
+    pub fn raw_elapsed(&self) -> f64 {
+        self.raw_elapsed
+    }
+    pub fn frame_count(&self) -> u64 {
+        self.frame_count
+    }
+    pub fn time_scale(&self) -> f32 {
+        self.time_scale
+    }
+    // 0.5 runs the game at half speed, negative scales are clamped to 0
+    pub fn set_time_scale(&mut self, time_scale: f32) {
+        self.time_scale = time_scale.max(0.0);
+    }
+    pub fn pause(&mut self) {
+        self.paused = true;
+    }
+    pub fn unpause(&mut self) {
+        self.paused = false;
+    }
+    pub fn is_paused(&self) -> bool {
+        self.paused
+    }
+    pub fn average_frame_time(&self) -> f32 {
+        self.average_frame_time
+    }
+    pub fn average_fps(&self) -> f32 {
+        if self.average_frame_time > 0.0 {
+            1.0 / self.average_frame_time
+        } else {
+            0.0
+        }
+    }
+    // How much weight the newest frame gets in average_frame_time, from 0 to 1
+    pub fn set_smoothing(&mut self, smoothing: f32) {
+        self.smoothing = smoothing.clamp(0.0, 1.0);
+    }
+}
+
+impl Default for Time {
+    fn default() -> Self {
+        Self::new()
+    }
+}
@@ -0,0 +1,217 @@
+use crate::adel_ecs::{Entity, RunStage, System, SystemResult, World};
+use crate::adel_time::Time;
+use anyhow::Context;
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum TimerMode {
+    // Finishes once and stays finished until reset
+    Once,
+    // Wraps back around every time the duration is reached
+    Repeating,
+}
+
+// Component counting up to a duration, ticked by the TimerSystem with the scaled frame delta so timers stop while
+// the game is paused. just_finished is only true for the frame the timer finished in
+#[derive(Debug, Clone)]
+pub struct Timer {
+    duration: f32,
+    elapsed: f32,
+    mode: TimerMode,
+    paused: bool,
+    finished: bool,
+    // How many times the duration was reached during the last tick, can be more than 1 for short repeating timers
+    times_finished_this_tick: u32,
+}
+
+impl Timer {
+    // duration is in seconds
+    pub fn new(duration: f32, mode: TimerMode) -> Self {
+        Self {
+            duration,
+            elapsed: 0.0,
+            mode,
+            paused: false,
+            finished: false,
+            times_finished_this_tick: 0,
+        }
+    }
+    pub fn once(duration: f32) -> Self {
+        Self::new(duration, TimerMode::Once)
+    }
+    pub fn repeating(duration: f32) -> Self {
+        Self::new(duration, TimerMode::Repeating)
+    }
+    pub fn tick(&mut self, dt: f32) -> &Self {
+        self.times_finished_this_tick = 0;
+        if self.paused || (self.mode == TimerMode::Once && self.finished) {
+            return self;
+        }
+        self.elapsed += dt;
+        if self.elapsed < self.duration {
+            return self;
+        }
+        self.finished = true;
+        match self.mode {
+            TimerMode::Once => {
+                self.elapsed = self.duration;
+                self.times_finished_this_tick = 1;
+            }
+            TimerMode::Repeating if self.duration > 0.0 => {
+                self.times_finished_this_tick = (self.elapsed / self.duration) as u32;
+                self.elapsed %= self.duration;
+            }
+            // A zero length repeating timer finishes every tick
+            TimerMode::Repeating => {
+                self.elapsed = 0.0;
+                self.times_finished_this_tick = 1;
+            }
+        }
+        self
+    }
+    pub fn just_finished(&self) -> bool {
+        self.times_finished_this_tick > 0
+    }
+    pub fn times_finished_this_tick(&self) -> u32 {
+        self.times_finished_this_tick
+    }
+    // Repeating timers count as finished from their first lap on
+    pub fn finished(&self) -> bool {
+        self.finished
+    }
+    pub fn elapsed(&self) -> f32 {
+        self.elapsed
+    }
+    pub fn remaining(&self) -> f32 {
+        (self.duration - self.elapsed).max(0.0)
+    }
+    // Progress of the current lap from 0 to 1
+    pub fn percent(&self) -> f32 {
+        if self.duration > 0.0 {
+            (self.elapsed / self.duration).clamp(0.0, 1.0)
+        } else {
+            1.0
+        }
+    }
+    pub fn duration(&self) -> f32 {
+        self.duration
+    }
+    pub fn set_duration(&mut self, duration: f32) {
+        self.duration = duration;
+    }
+    pub fn mode(&self) -> TimerMode {
+        self.mode
+    }
+    pub fn pause(&mut self) {
+        self.paused = true;
+    }
+    pub fn unpause(&mut self) {
+        self.paused = false;
+    }
+    pub fn is_paused(&self) -> bool {
+        self.paused
+    }
+    pub fn reset(&mut self) {
+        self.elapsed = 0.0;
+        self.finished = false;
+        self.times_finished_this_tick = 0;
+    }
+}
+
+// Component counting up from 0 with no end
+#[derive(Debug, Clone, Default)]
+pub struct Stopwatch {
+    elapsed: f32,
+    paused: bool,
+}
+
+impl Stopwatch {
+    pub fn new() -> Self {
+        Self::default()
+    }
+    pub fn tick(&mut self, dt: f32) -> &Self {
+        if !self.paused {
+            self.elapsed += dt;
+        }
+        self
+    }
+    pub fn elapsed(&self) -> f32 {
+        self.elapsed
+    }
+    pub fn pause(&mut self) {
+        self.paused = true;
+    }
+    pub fn unpause(&mut self) {
+        self.paused = false;
+    }
+    pub fn is_paused(&self) -> bool {
+        self.paused
+    }
+    pub fn reset(&mut self) {
+        self.elapsed = 0.0;
+    }
+}
+
+// Sent once per tick for every Timer that finished, times is how many laps a repeating timer finished in the tick
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct TimerFinished {
+    pub entity: Entity,
+    pub times: u32,
+}
+
+// Ticks every Timer and Stopwatch. Runs in EarlyUpdate so just_finished is already set for everything in Update
+pub struct TimerSystem {
+    name: &'static str,
+}
+
+impl TimerSystem {
+    pub fn new() -> Self {
+        Self {
+            name: "TimerSystem",
+        }
+    }
+}
+
+impl System for TimerSystem {
+    fn startup(&mut self, _world: &mut World) -> SystemResult {
+        Ok(())
+    }
+    fn run(&mut self, world: &mut World) -> SystemResult {
+        let dt = world
+            .get_resource::<Time>()
+            .context("TimerSystem needs the Time resource, add the CorePlugin")?
+            .delta();
+        let mut finished: Vec<TimerFinished> = Vec::new();
+        {
+            let mut timers = world
+                .query::<(Entity, &mut Timer)>()
+                .context("TimerSystem failed to query timers")?;
+            for (entity, timer) in timers.iter() {
+                if timer.tick(dt).just_finished() {
+                    finished.push(TimerFinished {
+                        entity,
+                        times: timer.times_finished_this_tick(),
+                    });
+                }
+            }
+            let mut stopwatches = world
+                .query::<&mut Stopwatch>()
+                .context("TimerSystem failed to query stopwatches")?;
+            for stopwatch in stopwatches.iter() {
+                stopwatch.tick(dt);
+            }
+        }
+        if let Some(mut writer) = world.event_writer::<TimerFinished>() {
+            writer.send_batch(finished);
+        }
+        Ok(())
+    }
+    fn shutdown(&mut self, _world: &mut World) -> SystemResult {
+        Ok(())
+    }
+    fn name(&self) -> &str {
+        self.name
+    }
+    fn get_run_stage(&self) -> RunStage {
+        RunStage::EarlyUpdate
+    }
+}
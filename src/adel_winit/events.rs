@@ -0,0 +1,38 @@
+use winit::event::{ElementState, VirtualKeyCode};
+
+// Window events forwarded from winit by the Application, each one is an Events<T> resource in the World
+
+// New inner size of the window in physical pixels
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct WindowResized {
+    pub width: u32,
+    pub height: u32,
+}
+
+// The user asked for the window to close, the Application exits right after so systems will only see this
+// from shutdown
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct WindowCloseRequested;
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct WindowFocused {
+    pub focused: bool,
+}
+
+// Raw key press or release, key_code is None for keys winit doesn't have a virtual key for
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct KeyboardEvent {
+    pub scan_code: u32,
+    pub key_code: Option<VirtualKeyCode>,
+    pub state: ElementState,
+}
+
+impl From<&winit::event::KeyboardInput> for KeyboardEvent {
+    fn from(input: &winit::event::KeyboardInput) -> Self {
+        Self {
+            scan_code: input.scancode,
+            key_code: input.virtual_keycode,
+            state: input.state,
+        }
+    }
+}
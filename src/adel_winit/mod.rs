@@ -1,3 +1,7 @@
+mod events;
+mod plugin;
 mod window;
 
+pub use events::*;
+pub use plugin::*;
 pub use window::*;
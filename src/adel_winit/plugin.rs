@@ -0,0 +1,17 @@
+use crate::adel_app::{AppBuilder, Plugin};
+use crate::adel_winit::WinitWindow;
+
+// Opens the window, hands its event loop to the Application and makes the Window available as a resource
+pub struct WindowPlugin;
+
+impl Plugin for WindowPlugin {
+    fn build(&self, app: &mut AppBuilder) {
+        // WinitWindow handles boiler plate setup for windowing/event_loop
+        let mut winit_window = WinitWindow::new();
+        let event_loop = winit_window.event_loop().unwrap();
+        let window = winit_window.window().unwrap();
+        app.set_event_loop(event_loop)
+            .insert_resource(window)
+            .add_system(winit_window);
+    }
+}
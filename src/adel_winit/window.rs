@@ -1,4 +1,4 @@
-use crate::adel_ecs::{System, World};
+use crate::adel_ecs::{System, SystemResult, World};
 use winit::{
     event_loop::EventLoop,
     window::{Window, WindowBuilder},
@@ -46,12 +46,17 @@ impl WinitWindow {
 }
 
 impl System for WinitWindow {
-    fn startup(&mut self, _world: &mut World) {}
-    fn run(&mut self, world: &mut World) {
+    fn startup(&mut self, _world: &mut World) -> SystemResult {
+        Ok(())
+    }
+    fn run(&mut self, world: &mut World) -> SystemResult {
         let window = world.get_resource::<Window>().unwrap();
         window.request_redraw();
+        Ok(())
+    }
+    fn shutdown(&mut self, _world: &mut World) -> SystemResult {
+        Ok(())
     }
-    fn shutdown(&mut self, _world: &mut World) {}
     fn name(&self) -> &'static str {
         self.name
     }
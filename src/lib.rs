@@ -4,6 +4,7 @@ mod adel_ecs;
 mod adel_input;
 mod adel_physics;
 mod adel_renderer;
+mod adel_time;
 mod adel_tools;
 mod adel_winit;
 
@@ -25,6 +26,9 @@ pub mod physics {
 pub mod renderer {
     pub use crate::adel_renderer::*;
 }
+pub mod time {
+    pub use crate::adel_time::*;
+}
 pub mod tools {
     pub use crate::adel_tools::*;
 }
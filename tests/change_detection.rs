@@ -0,0 +1,122 @@
+mod common;
+
+use adel::app::Application;
+use adel::ecs::{Added, Changed, Entity, RunStage, World};
+use common::{probe, TestSystem};
+use std::cell::RefCell;
+use std::rc::Rc;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct Position(i32);
+
+// Entities with an added, changed and removed Position, in query order
+#[derive(Debug, Default, Clone, PartialEq)]
+struct Changes {
+    added: Vec<Entity>,
+    changed: Vec<Entity>,
+    removed: Vec<Entity>,
+}
+
+fn changes(world: &World) -> Changes {
+    let mut added = world.query_filtered::<Entity, Added<Position>>().unwrap();
+    let mut changed = world.query_filtered::<Entity, Changed<Position>>().unwrap();
+    Changes {
+        added: added.iter().collect(),
+        changed: changed.iter().collect(),
+        removed: world.removed::<Position>(),
+    }
+}
+
+fn frame(added: &[Entity], changed: &[Entity], removed: &[Entity]) -> Changes {
+    Changes {
+        added: added.to_vec(),
+        changed: changed.to_vec(),
+        removed: removed.to_vec(),
+    }
+}
+
+// a and b start out with a Position. In Update a is written to on the second frame, when c also gets a
+// Position, and b loses its Position on the third. Probes run before the writer in EarlyUpdate and after it in
+// LateUpdate
+fn change_app() -> (
+    Application,
+    [Entity; 3],
+    Rc<RefCell<Vec<Changes>>>,
+    Rc<RefCell<Vec<Changes>>>,
+) {
+    let mut world = World::new();
+    let [a, b, c] = [world.new_entity(), world.new_entity(), world.new_entity()];
+    world.add_component_to_entity(a, Position(1));
+    world.add_component_to_entity(b, Position(2));
+    let mut app = Application::headless(world);
+    let mut frame = 0;
+    app.add_system(TestSystem::new("Writer", RunStage::Update, move |world| {
+        frame += 1;
+        match frame {
+            2 => {
+                world.get_component_mut::<Position>(a).unwrap().0 += 10;
+                world.add_component_to_entity(c, Position(3));
+            }
+            3 => {
+                world.remove_component::<Position>(b);
+            }
+            _ => {}
+        }
+        Ok(())
+    }));
+    let (early, before) = probe("EarlyProbe", RunStage::EarlyUpdate, changes);
+    let (late, after) = probe("LateProbe", RunStage::LateUpdate, changes);
+    app.add_system(early);
+    app.add_system(late);
+    (app, [a, b, c], before, after)
+}
+
+#[test]
+fn changes_are_seen_once_by_systems_running_after_them() {
+    let (mut app, [a, b, c], _before, after) = change_app();
+    app.run_frames(4).unwrap();
+    assert_eq!(
+        *after.borrow(),
+        vec![
+            // a and b were already there for startup, they aren't new to run
+            frame(&[], &[], &[]),
+            frame(&[c], &[a, c], &[]),
+            frame(&[], &[], &[b]),
+            frame(&[], &[], &[]),
+        ]
+    );
+    app.exit();
+}
+
+#[test]
+fn changes_are_seen_next_frame_by_systems_running_before_them() {
+    let (mut app, [a, b, c], before, _after) = change_app();
+    app.run_frames(5).unwrap();
+    assert_eq!(
+        *before.borrow(),
+        vec![
+            frame(&[], &[], &[]),
+            frame(&[], &[], &[]),
+            frame(&[c], &[a, c], &[]),
+            frame(&[], &[], &[b]),
+            frame(&[], &[], &[]),
+        ]
+    );
+    app.exit();
+}
+
+#[test]
+fn clear_trackers_drops_removals_after_a_frame() {
+    let (mut app, [_a, b, _c], _before, _after) = change_app();
+    // Looking from before the first system ever ran, every removal still stored shows up
+    let stored_removals = |app: &mut Application| {
+        app.world.set_last_change_tick(0);
+        app.world.removed::<Position>()
+    };
+    app.run_frames(3).unwrap();
+    assert_eq!(stored_removals(&mut app), vec![b]);
+    // Kept through the end of the next frame so the EarlyUpdate probe gets to see it
+    app.run_frame().unwrap();
+    assert!(stored_removals(&mut app).is_empty());
+    app.exit();
+}
@@ -0,0 +1,103 @@
+// Systems shared by the integration tests. Every test binary only uses some of them
+#![allow(dead_code)]
+
+use adel::app::AppExit;
+use adel::ecs::{RunStage, System, SystemResult, World};
+use std::cell::RefCell;
+use std::rc::Rc;
+
+// What a TestSystem went through, shared with the test so it can still be read after the Application is gone
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub struct Calls {
+    pub startup: u32,
+    pub run: u32,
+    pub shutdown: u32,
+}
+
+impl Calls {
+    pub fn new(startup: u32, run: u32, shutdown: u32) -> Self {
+        Self {
+            startup,
+            run,
+            shutdown,
+        }
+    }
+}
+
+// Runs a closure every frame and counts its calls
+pub struct TestSystem {
+    name: &'static str,
+    stage: RunStage,
+    calls: Rc<RefCell<Calls>>,
+    run: Box<dyn FnMut(&mut World) -> SystemResult>,
+}
+
+impl TestSystem {
+    pub fn new(
+        name: &'static str,
+        stage: RunStage,
+        run: impl FnMut(&mut World) -> SystemResult + 'static,
+    ) -> Self {
+        Self {
+            name,
+            stage,
+            calls: Rc::new(RefCell::new(Calls::default())),
+            run: Box::new(run),
+        }
+    }
+    pub fn calls(&self) -> Rc<RefCell<Calls>> {
+        self.calls.clone()
+    }
+}
+
+impl System for TestSystem {
+    fn startup(&mut self, _world: &mut World) -> SystemResult {
+        self.calls.borrow_mut().startup += 1;
+        Ok(())
+    }
+    fn run(&mut self, world: &mut World) -> SystemResult {
+        self.calls.borrow_mut().run += 1;
+        (self.run)(world)
+    }
+    fn shutdown(&mut self, _world: &mut World) -> SystemResult {
+        self.calls.borrow_mut().shutdown += 1;
+        Ok(())
+    }
+    fn name(&self) -> &str {
+        self.name
+    }
+    fn get_run_stage(&self) -> RunStage {
+        self.stage
+    }
+}
+
+// Update system that sends AppExit on its exit_after-th run, or never
+pub fn counter(exit_after: Option<u32>) -> TestSystem {
+    let mut runs = 0;
+    TestSystem::new("Counter", RunStage::Update, move |world| {
+        runs += 1;
+        if Some(runs) == exit_after {
+            world.send_event(AppExit);
+        }
+        Ok(())
+    })
+}
+
+// System keeping whatever see returns every frame it runs
+pub fn probe<T: 'static>(
+    name: &'static str,
+    stage: RunStage,
+    mut see: impl FnMut(&World) -> T + 'static,
+) -> (TestSystem, Rc<RefCell<Vec<T>>>) {
+    let seen = Rc::new(RefCell::new(Vec::new()));
+    let frames = seen.clone();
+    let system = TestSystem::new(name, stage, move |world| {
+        frames.borrow_mut().push(see(world));
+        Ok(())
+    });
+    (system, seen)
+}
+
+pub fn last_seen<T: Clone>(seen: &Rc<RefCell<Vec<T>>>) -> T {
+    seen.borrow().last().cloned().unwrap()
+}
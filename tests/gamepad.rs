@@ -0,0 +1,236 @@
+mod common;
+
+use adel::app::Application;
+use adel::ecs::{EventReader, Events, RunStage, World};
+use adel::input::{
+    GamepadAxis, GamepadButton, GamepadEvent, GamepadId, InputConsumer, InputPlayback,
+    InputRecording, InputSources, SyntheticInputHandle, SyntheticInputSource,
+};
+use common::{last_seen, probe};
+use std::cell::RefCell;
+use std::rc::Rc;
+
+const PAD: GamepadId = GamepadId(0);
+
+// What the Update systems saw of the South button and the gamepad events in one frame
+#[derive(Debug, Default, Clone, PartialEq)]
+struct Seen {
+    connected: Vec<(GamepadId, String)>,
+    pressed: bool,
+    just_pressed: bool,
+    just_released: bool,
+    events: Vec<GamepadEvent>,
+}
+
+// Headless Application reading a SyntheticInputSource, with a probe recording every frame
+fn gamepad_app(world: World) -> (Application, SyntheticInputHandle, Rc<RefCell<Vec<Seen>>>) {
+    let (source, handle) = SyntheticInputSource::new();
+    let mut world = world;
+    let mut sources = InputSources::new();
+    sources.add(source);
+    world.insert_resource(sources);
+    let mut app = Application::headless(world);
+    let mut reader = EventReader::new();
+    let (probe, seen) = probe("Probe", RunStage::Update, move |world| {
+        let input = world.get_resource::<InputConsumer>().unwrap();
+        let events = world.get_resource::<Events<GamepadEvent>>().unwrap();
+        Seen {
+            connected: input
+                .gamepads()
+                .map(|(id, gamepad)| (id, gamepad.name().to_owned()))
+                .collect(),
+            pressed: input.is_gamepad_pressed(GamepadButton::South),
+            just_pressed: input.is_gamepad_just_pressed(GamepadButton::South),
+            just_released: input.is_gamepad_just_released(GamepadButton::South),
+            events: reader.read(&events).cloned().collect(),
+        }
+    });
+    app.add_system(probe);
+    (app, handle, seen)
+}
+
+#[test]
+fn synthetic_source_reaches_the_input_consumer() {
+    let (mut app, handle, seen) = gamepad_app(World::new());
+
+    handle.connect(PAD, "Synthetic Pad");
+    app.run_frame().unwrap();
+    let frame = last_seen(&seen);
+    assert_eq!(frame.connected, vec![(PAD, "Synthetic Pad".to_owned())]);
+    assert!(!frame.pressed);
+    assert_eq!(
+        frame.events,
+        vec![GamepadEvent::Connected {
+            id: PAD,
+            name: "Synthetic Pad".to_owned()
+        }]
+    );
+
+    handle.press(PAD, GamepadButton::South);
+    app.run_frame().unwrap();
+    let frame = last_seen(&seen);
+    assert!(frame.pressed && frame.just_pressed && !frame.just_released);
+
+    // Still held, no longer just pressed
+    app.run_frame().unwrap();
+    let frame = last_seen(&seen);
+    assert!(frame.pressed && !frame.just_pressed);
+    assert!(frame.events.is_empty());
+
+    handle.release(PAD, GamepadButton::South);
+    app.run_frame().unwrap();
+    let frame = last_seen(&seen);
+    assert!(!frame.pressed && frame.just_released);
+
+    // Input from a controller that never connected is dropped
+    handle.press(GamepadId(3), GamepadButton::South);
+    app.run_frame().unwrap();
+    let frame = last_seen(&seen);
+    assert!(!frame.pressed);
+    assert_eq!(frame.connected.len(), 1);
+
+    handle.disconnect(PAD);
+    app.run_frame().unwrap();
+    let frame = last_seen(&seen);
+    assert!(frame.connected.is_empty());
+    assert_eq!(frame.events, vec![GamepadEvent::Disconnected { id: PAD }]);
+    app.exit();
+}
+
+fn assert_close(value: f32, expected: f32) {
+    assert!(
+        (value - expected).abs() < 1e-5,
+        "{} isn't close to {}",
+        value,
+        expected
+    );
+}
+
+#[test]
+fn axes_have_a_dead_zone() {
+    let (mut app, handle, _seen) = gamepad_app(World::new());
+    handle.connect(PAD, "Pad");
+    let mut axis = |value: f32| {
+        handle.set_axis(PAD, GamepadAxis::LeftTrigger, value);
+        app.run_frame().unwrap();
+        let input = app.world.get_resource::<InputConsumer>().unwrap();
+        let gamepad = input.gamepad(PAD).unwrap();
+        assert_eq!(gamepad.raw_axis(GamepadAxis::LeftTrigger), value);
+        gamepad.axis(GamepadAxis::LeftTrigger)
+    };
+    // The default dead zone is 0.15, what's left of the range is stretched back to 0..1
+    assert_close(axis(0.1), 0.0);
+    assert_close(axis(0.15), 0.0);
+    assert_close(axis(0.575), 0.5);
+    assert_close(axis(1.0), 1.0);
+    assert_close(axis(-0.575), -0.5);
+}
+
+#[test]
+fn sticks_have_a_radial_dead_zone() {
+    let (mut app, handle, _seen) = gamepad_app(World::new());
+    handle.connect(PAD, "Pad");
+    let mut stick = |x: f32, y: f32| {
+        handle.set_axis(PAD, GamepadAxis::LeftStickX, x);
+        handle.set_axis(PAD, GamepadAxis::LeftStickY, y);
+        app.run_frame().unwrap();
+        let input = app.world.get_resource::<InputConsumer>().unwrap();
+        input.gamepad(PAD).unwrap().left_stick()
+    };
+
+    // Both axes are past the dead zone on their own but too short together
+    let inside = stick(0.1, 0.1);
+    assert_close(inside.norm(), 0.0);
+
+    // Length 0.5 shrinks to (0.5 - 0.15) / 0.85 without turning, per axis dead zones would have bent it
+    let diagonal = stick(0.3, 0.4);
+    assert_close(diagonal.norm(), 0.35 / 0.85);
+    assert_close(diagonal.x / diagonal.y, 0.75);
+
+    let full = stick(0.6, 0.8);
+    assert_close(full.x, 0.6);
+    assert_close(full.y, 0.8);
+
+    // Controllers reporting past 1 are clamped to the unit circle
+    let over = stick(1.2, 0.0);
+    assert_close(over.x, 1.0);
+    assert_close(over.y, 0.0);
+}
+
+#[test]
+fn dead_zone_can_be_changed_per_controller() {
+    let (mut app, handle, _seen) = gamepad_app(World::new());
+    handle.connect(PAD, "Pad");
+    handle.set_axis(PAD, GamepadAxis::RightStickX, 0.2);
+    app.run_frame().unwrap();
+    let mut input = app.world.get_resource_mut::<InputConsumer>().unwrap();
+    let gamepad = input.gamepad_mut(PAD).unwrap();
+    assert_close(gamepad.axis(GamepadAxis::RightStickX), 0.05 / 0.85);
+    gamepad.set_dead_zone(0.25);
+    assert_close(gamepad.axis(GamepadAxis::RightStickX), 0.0);
+    // Never all the way to 1, nothing would get through
+    gamepad.set_dead_zone(2.0);
+    assert_close(gamepad.dead_zone(), 0.99);
+}
+
+#[test]
+fn live_gamepads_come_back_after_playback() {
+    let mut recorded = InputConsumer::new();
+    recorded.capture_gamepad_event(&GamepadEvent::Connected {
+        id: GamepadId(5),
+        name: "Recorded Pad".to_owned(),
+    });
+    let mut recording = InputRecording::new();
+    recording.push(1.0 / 60.0, &recorded);
+    recording.push(1.0 / 60.0, &recorded);
+    let mut world = World::new();
+    world.insert_resource(InputPlayback::new(recording).with_exit_when_done(false));
+    let (mut app, handle, seen) = gamepad_app(world);
+
+    // Plugged in while the recording plays, the recorded controller is all the systems see
+    handle.connect(PAD, "Live Pad");
+    app.run_frame().unwrap();
+    assert_eq!(
+        last_seen(&seen).connected,
+        vec![(GamepadId(5), "Recorded Pad".to_owned())]
+    );
+    handle.press(PAD, GamepadButton::South);
+    handle.set_axis(PAD, GamepadAxis::LeftTrigger, 1.0);
+    app.run_frame().unwrap();
+    assert!(!last_seen(&seen).pressed);
+
+    // Whatever is still held when the recording ends carries over to the live controller
+    app.run_frame().unwrap();
+    let frame = last_seen(&seen);
+    assert_eq!(frame.connected, vec![(PAD, "Live Pad".to_owned())]);
+    assert!(frame.pressed && frame.just_pressed);
+    assert_eq!(
+        frame.events,
+        vec![
+            GamepadEvent::Disconnected { id: GamepadId(5) },
+            GamepadEvent::Connected {
+                id: PAD,
+                name: "Live Pad".to_owned()
+            },
+            GamepadEvent::ButtonPressed {
+                id: PAD,
+                button: GamepadButton::South
+            },
+            GamepadEvent::AxisChanged {
+                id: PAD,
+                axis: GamepadAxis::LeftTrigger,
+                value: 1.0
+            },
+        ]
+    );
+    {
+        let input = app.world.get_resource::<InputConsumer>().unwrap();
+        let gamepad = input.gamepad(PAD).unwrap();
+        assert_close(gamepad.axis(GamepadAxis::LeftTrigger), 1.0);
+    }
+    handle.release(PAD, GamepadButton::South);
+    app.run_frame().unwrap();
+    let frame = last_seen(&seen);
+    assert!(!frame.pressed && frame.just_released);
+    app.exit();
+}
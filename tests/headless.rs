@@ -0,0 +1,43 @@
+mod common;
+
+use adel::app::Application;
+use adel::ecs::World;
+use adel::time::Time;
+use common::{counter, Calls};
+
+#[test]
+fn runs_max_frames_then_shuts_down() {
+    let mut app = Application::headless(World::new());
+    let counter = counter(None);
+    let calls = counter.calls();
+    app.add_system(counter);
+    let world = app.run_headless(1.0 / 60.0, Some(10)).unwrap();
+
+    assert_eq!(*calls.borrow(), Calls::new(1, 10, 1));
+    let time = world.get_resource::<Time>().unwrap();
+    assert_eq!(time.frame_count(), 10);
+    assert!((time.elapsed() - 10.0 / 60.0).abs() < 1e-4);
+}
+
+#[test]
+fn app_exit_stops_at_the_end_of_the_frame() {
+    let mut app = Application::headless(World::new());
+    let counter = counter(Some(4));
+    let calls = counter.calls();
+    app.add_system(counter);
+    app.run_headless(1.0 / 60.0, Some(100)).unwrap();
+
+    assert_eq!(*calls.borrow(), Calls::new(1, 4, 1));
+}
+
+#[test]
+fn app_exit_without_a_frame_limit() {
+    let mut app = Application::headless(World::new());
+    let counter = counter(Some(25));
+    let calls = counter.calls();
+    app.add_system(counter);
+    let world = app.run_headless(0.5, None).unwrap();
+
+    assert_eq!(calls.borrow().run, 25);
+    assert_eq!(world.get_resource::<Time>().unwrap().frame_count(), 25);
+}
@@ -0,0 +1,67 @@
+use adel::app::{Application, CorePlugin, ExitStatus};
+use adel::ecs::{SystemErrors, SystemPhase, World};
+use adel::input::{InputRecorder, InputSourceSystem, KeyboardCameraPlugin, QuitOnAction};
+use adel::time::TimerSystem;
+
+// Missing resources come back as system errors instead of panics
+#[test]
+fn keyboard_camera_without_the_input_plugin() {
+    let mut builder = Application::builder(World::new());
+    builder
+        .add_plugin(CorePlugin)
+        .add_plugin(KeyboardCameraPlugin);
+    let mut app = builder.build();
+    assert_eq!(app.run_frames(3).unwrap(), None);
+    assert_eq!(app.exit(), ExitStatus::Success);
+
+    let errors = app.world.get_resource::<SystemErrors>().unwrap();
+    let failed_to_run = |label: &str| {
+        errors
+            .iter()
+            .any(|error| error.label == label && matches!(error.phase, SystemPhase::Run(_)))
+    };
+    assert!(failed_to_run("KeyboardHandler"));
+    assert!(failed_to_run("FlyCameraController"));
+    assert!(failed_to_run("OrbitCameraController"));
+}
+
+#[test]
+fn plugin_systems_without_their_plugins() {
+    let path = std::env::temp_dir().join("adel_plugins_test_recording.txt");
+    // No CorePlugin, only the SystemErrors resource it would have added
+    let mut world = World::new();
+    world.insert_resource(SystemErrors::new());
+    let mut builder = Application::builder(world);
+    builder
+        .add_system(InputRecorder::new(&path))
+        .add_system(QuitOnAction::new())
+        .add_system(InputSourceSystem::new())
+        .add_system(TimerSystem::new());
+    let mut app = builder.build();
+    assert_eq!(app.run_frames(3).unwrap(), None);
+    assert_eq!(app.exit(), ExitStatus::Success);
+    let _ = std::fs::remove_file(&path);
+
+    let errors = app.world.get_resource::<SystemErrors>().unwrap();
+    for label in [
+        "InputRecorder",
+        "QuitOnAction",
+        "InputSourceSystem",
+        "TimerSystem",
+    ] {
+        assert!(
+            errors
+                .iter()
+                .any(|error| error.label == label && matches!(error.phase, SystemPhase::Run(_))),
+            "{} didn't report an error",
+            label
+        );
+    }
+}
+
+#[test]
+fn runs_without_the_core_plugin() {
+    let mut app = Application::builder(World::new()).build();
+    assert_eq!(app.run_frames(3).unwrap(), None);
+    assert_eq!(app.exit(), ExitStatus::Success);
+}
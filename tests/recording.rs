@@ -0,0 +1,171 @@
+mod common;
+
+use adel::app::Application;
+use adel::ecs::{RunStage, World};
+use adel::input::{
+    GamepadAxis, GamepadButton, GamepadEvent, GamepadId, InputConsumer, InputPlayback,
+    InputRecording,
+};
+use adel::time::Time;
+use common::probe;
+use std::cell::RefCell;
+use std::rc::Rc;
+use winit::dpi::{PhysicalPosition, PhysicalSize};
+use winit::event::{ElementState, KeyboardInput, MouseButton, VirtualKeyCode};
+
+fn key(state: ElementState, key_code: VirtualKeyCode) -> KeyboardInput {
+    #[allow(deprecated)]
+    KeyboardInput {
+        scancode: 0,
+        state,
+        virtual_keycode: Some(key_code),
+        modifiers: Default::default(),
+    }
+}
+
+// A frame with a bit of everything, the gamepad's name has the characters that used to get lost
+fn busy_input(pad_name: &str) -> InputConsumer {
+    let mut input = InputConsumer::new();
+    input.capture_keyboard_input(&key(ElementState::Pressed, VirtualKeyCode::W));
+    input.capture_keyboard_input(&key(ElementState::Pressed, VirtualKeyCode::LShift));
+    input.capture_mouse_button(ElementState::Pressed, MouseButton::Left);
+    input.capture_cursor_moved(PhysicalPosition::new(120.0, 80.5));
+    input.set_window_size(PhysicalSize::new(800, 600));
+    input.capture_mouse_motion((3.0, -1.0));
+    input.capture_gamepad_event(&GamepadEvent::Connected {
+        id: GamepadId(2),
+        name: pad_name.to_owned(),
+    });
+    input.capture_gamepad_event(&GamepadEvent::ButtonPressed {
+        id: GamepadId(2),
+        button: GamepadButton::South,
+    });
+    input.capture_gamepad_event(&GamepadEvent::AxisChanged {
+        id: GamepadId(2),
+        axis: GamepadAxis::LeftStickX,
+        value: -0.25,
+    });
+    input
+}
+
+#[test]
+fn recordings_round_trip() {
+    let pad_name = "Pad  #2 \"Pro\" C:\\pads";
+    let mut input = busy_input(pad_name);
+    let mut recording = InputRecording::new();
+    recording.push(1.0 / 60.0, &input);
+    input.end_frame();
+    input.capture_keyboard_input(&key(ElementState::Released, VirtualKeyCode::W));
+    input.capture_focus(false);
+    recording.push(0.02, &input);
+
+    let text = recording.to_recording_string();
+    let parsed = InputRecording::parse(&text).unwrap();
+    assert_eq!(parsed.to_recording_string(), text);
+    assert_eq!(parsed.len(), 2);
+
+    let first = &parsed.frames()[0];
+    assert_eq!(first.dt, 1.0 / 60.0);
+    assert!(first.input.is_key_just_pressed(VirtualKeyCode::W));
+    assert!(first.input.is_key_pressed(VirtualKeyCode::LShift));
+    assert!(first.input.is_mouse_just_pressed(MouseButton::Left));
+    assert_eq!(first.input.cursor_position().unwrap().y, 80.5);
+    assert_eq!(first.input.mouse_delta().x, 3.0);
+    let gamepad = first.input.gamepad(GamepadId(2)).unwrap();
+    assert_eq!(gamepad.name(), pad_name);
+    assert!(gamepad.is_just_pressed(GamepadButton::South));
+    assert_eq!(gamepad.raw_axis(GamepadAxis::LeftStickX), -0.25);
+
+    let second = &parsed.frames()[1];
+    assert_eq!(second.dt, 0.02);
+    assert!(second.input.is_key_just_released(VirtualKeyCode::W));
+    assert!(!second.input.is_key_pressed(VirtualKeyCode::W));
+    assert!(!second.input.is_focused());
+    assert_eq!(second.input.mouse_delta().x, 0.0);
+    assert_eq!(second.input.gamepad(GamepadId(2)).unwrap().name(), pad_name);
+}
+
+#[test]
+fn comments_and_quotes_are_parsed() {
+    let recording = InputRecording::parse(
+        "# made by hand\n\
+         adel_input_recording 2\n\
+         frame 0.5 # slow\n\
+         keys A\n\
+         pad 1 0.2 \"#1 \\\\ \\\"pad\\\"\" # not part of the name\n",
+    )
+    .unwrap();
+    let input = &recording.frames()[0].input;
+    assert!(input.is_key_pressed(VirtualKeyCode::A));
+    assert_eq!(input.gamepad(GamepadId(1)).unwrap().name(), "#1 \\ \"pad\"");
+
+    let error =
+        InputRecording::parse("adel_input_recording 2\nframe 0.5\npad 1 0.2 \"Pad\n").unwrap_err();
+    assert_eq!(error.line, 3);
+    let error = InputRecording::parse("adel_input_recording 1\nframe 0.5\n").unwrap_err();
+    assert_eq!(error.line, 1);
+}
+
+// W pressed for two frames then released, every frame a different length
+fn w_for_two_frames() -> InputRecording {
+    let mut input = InputConsumer::new();
+    let mut recording = InputRecording::new();
+    input.capture_keyboard_input(&key(ElementState::Pressed, VirtualKeyCode::W));
+    recording.push(0.01, &input);
+    input.end_frame();
+    recording.push(0.02, &input);
+    input.end_frame();
+    input.capture_keyboard_input(&key(ElementState::Released, VirtualKeyCode::W));
+    recording.push(0.03, &input);
+    recording
+}
+
+// What the Update systems saw of the W key and the frame time
+fn playback_app(playback: InputPlayback) -> (Application, Rc<RefCell<Vec<(bool, bool, f32)>>>) {
+    let mut world = World::new();
+    world.insert_resource(playback);
+    let mut app = Application::headless(world);
+    let (probe, seen) = probe("Probe", RunStage::Update, |world| {
+        let input = world.get_resource::<InputConsumer>().unwrap();
+        let time = world.get_resource::<Time>().unwrap();
+        (
+            input.is_key_pressed(VirtualKeyCode::W),
+            input.is_key_just_pressed(VirtualKeyCode::W),
+            time.raw_delta(),
+        )
+    });
+    app.add_system(probe);
+    (app, seen)
+}
+
+#[test]
+fn playback_drives_a_headless_app_then_exits() {
+    let (app, seen) = playback_app(InputPlayback::new(w_for_two_frames()));
+    let world = app.run_headless(1.0 / 60.0, Some(10)).unwrap();
+
+    assert_eq!(
+        *seen.borrow(),
+        vec![
+            (true, true, 0.01),
+            (true, false, 0.02),
+            (false, false, 0.03)
+        ]
+    );
+    let playback = world.get_resource::<InputPlayback>().unwrap();
+    assert_eq!(playback.frame(), 3);
+    assert_eq!(world.get_resource::<Time>().unwrap().frame_count(), 3);
+}
+
+#[test]
+fn playback_hands_back_to_live_input() {
+    let (app, seen) =
+        playback_app(InputPlayback::new(w_for_two_frames()).with_exit_when_done(false));
+    let world = app.run_headless(1.0 / 60.0, Some(5)).unwrap();
+
+    let seen = seen.borrow();
+    assert_eq!(seen.len(), 5);
+    assert_eq!(seen[2], (false, false, 0.03));
+    assert_eq!(seen[3], (false, false, 1.0 / 60.0));
+    assert_eq!(seen[4], (false, false, 1.0 / 60.0));
+    assert!(!world.get_resource::<InputPlayback>().unwrap().is_playing());
+}
@@ -0,0 +1,133 @@
+mod common;
+
+use adel::app::{AppExit, Application, ExitStatus};
+use adel::ecs::{ErrorPolicy, IntoSystemDescriptor, RunStage, ScheduleError, World};
+use common::{counter, Calls, TestSystem};
+use std::cell::RefCell;
+use std::rc::Rc;
+
+fn stepped_app(exit_after: Option<u32>) -> (Application, Rc<RefCell<Calls>>) {
+    let mut app = Application::headless(World::new());
+    let counter = counter(exit_after);
+    let calls = counter.calls();
+    app.add_system(counter);
+    (app, calls)
+}
+
+#[test]
+fn run_frame_starts_up_on_the_first_frame() {
+    let (mut app, calls_made) = stepped_app(None);
+    assert!(!app.is_running());
+    assert_eq!(app.run_frame().unwrap(), None);
+    assert!(app.is_running());
+    assert_eq!(*calls_made.borrow(), Calls::new(1, 1, 0));
+    assert_eq!(app.run_frame().unwrap(), None);
+    assert_eq!(*calls_made.borrow(), Calls::new(1, 2, 0));
+}
+
+#[test]
+fn run_frames_stops_early_on_app_exit() {
+    let (mut app, calls_made) = stepped_app(Some(3));
+    assert_eq!(app.run_frames(10).unwrap(), Some(ExitStatus::Success));
+    assert_eq!(*calls_made.borrow(), Calls::new(1, 3, 1));
+    assert_eq!(app.exit_status(), Some(ExitStatus::Success));
+
+    // Once exited every call hands back the status without running anything
+    assert_eq!(app.run_frame().unwrap(), Some(ExitStatus::Success));
+    assert_eq!(app.run_frames(5).unwrap(), Some(ExitStatus::Success));
+    assert_eq!(app.exit(), ExitStatus::Success);
+    assert_eq!(*calls_made.borrow(), Calls::new(1, 3, 1));
+}
+
+#[test]
+fn exit_shuts_down_once() {
+    let (mut app, calls_made) = stepped_app(None);
+    assert_eq!(app.run_frames(4).unwrap(), None);
+    assert_eq!(app.exit(), ExitStatus::Success);
+    assert!(!app.is_running());
+    assert_eq!(app.exit(), ExitStatus::Success);
+    assert_eq!(*calls_made.borrow(), Calls::new(1, 4, 1));
+}
+
+#[test]
+fn exit_before_the_first_frame_skips_startup_and_shutdown() {
+    let (mut app, calls_made) = stepped_app(None);
+    assert_eq!(app.exit(), ExitStatus::Success);
+    assert_eq!(app.run_frame().unwrap(), Some(ExitStatus::Success));
+    assert_eq!(*calls_made.borrow(), Calls::new(0, 0, 0));
+}
+
+#[test]
+fn dropping_a_running_app_shuts_it_down() {
+    let (mut app, calls_made) = stepped_app(None);
+    assert_eq!(app.run_frames(2).unwrap(), None);
+    drop(app);
+    assert_eq!(*calls_made.borrow(), Calls::new(1, 2, 1));
+
+    // Never started, nothing to shut down
+    let (app, calls_made) = stepped_app(None);
+    drop(app);
+    assert_eq!(*calls_made.borrow(), Calls::new(0, 0, 0));
+}
+
+#[test]
+fn systems_added_between_frames_are_started_before_they_run() {
+    let (mut app, calls_made) = stepped_app(None);
+    assert_eq!(app.run_frames(2).unwrap(), None);
+    let late = TestSystem::new("Late", RunStage::Update, |_| Ok(()));
+    let late_calls = late.calls();
+    app.add_system(late);
+    assert_eq!(app.run_frame().unwrap(), None);
+    assert_eq!(*late_calls.borrow(), Calls::new(1, 1, 0));
+    // Systems that were already running aren't started again
+    assert_eq!(*calls_made.borrow(), Calls::new(1, 3, 0));
+    app.exit();
+    assert_eq!(*late_calls.borrow(), Calls::new(1, 1, 1));
+    assert_eq!(*calls_made.borrow(), Calls::new(1, 3, 1));
+}
+
+#[test]
+fn systems_added_between_frames_that_break_the_schedule_are_reported() {
+    let (mut app, calls_made) = stepped_app(None);
+    assert_eq!(app.run_frame().unwrap(), None);
+    let duplicate = counter(None);
+    let duplicate_calls = duplicate.calls();
+    app.add_system(duplicate);
+    assert_eq!(
+        app.run_frame(),
+        Err(ScheduleError::DuplicateLabel("Counter".to_owned()))
+    );
+    // The systems that were running are still shut down, the one that never started isn't
+    app.exit();
+    assert_eq!(*calls_made.borrow(), Calls::new(1, 1, 1));
+    assert_eq!(*duplicate_calls.borrow(), Calls::new(0, 0, 0));
+}
+
+// LateUpdate system that sends AppExit on its second run, or fails if fail is set
+fn late_exit(fail: bool) -> TestSystem {
+    let mut runs = 0;
+    TestSystem::new("LateExit", RunStage::LateUpdate, move |world| {
+        runs += 1;
+        if runs < 2 {
+            return Ok(());
+        }
+        if fail {
+            return Err(anyhow::anyhow!("Failed in LateUpdate"));
+        }
+        world.send_event(AppExit);
+        Ok(())
+    })
+}
+
+#[test]
+fn exits_from_late_update_are_honored_the_same_frame() {
+    let (mut app, calls_made) = stepped_app(None);
+    app.add_system(late_exit(false));
+    assert_eq!(app.run_frames(5).unwrap(), Some(ExitStatus::Success));
+    assert_eq!(*calls_made.borrow(), Calls::new(1, 2, 1));
+
+    let (mut app, calls_made) = stepped_app(None);
+    app.add_system(late_exit(true).on_error(ErrorPolicy::Shutdown));
+    assert_eq!(app.run_frames(5).unwrap(), Some(ExitStatus::Failure));
+    assert_eq!(*calls_made.borrow(), Calls::new(1, 2, 1));
+}
@@ -0,0 +1,80 @@
+mod common;
+
+use adel::app::{Application, ExitStatus};
+use adel::ecs::{IntoSystemDescriptor, RunStage, World};
+use adel::input::{not_typing, InputConsumer, InputMap, TextInput};
+use common::{last_seen, probe};
+use std::cell::RefMut;
+use winit::event::{ElementState, KeyboardInput, VirtualKeyCode};
+
+fn key(state: ElementState, key_code: VirtualKeyCode) -> KeyboardInput {
+    #[allow(deprecated)]
+    KeyboardInput {
+        scancode: 0,
+        state,
+        virtual_keycode: Some(key_code),
+        modifiers: Default::default(),
+    }
+}
+
+// What the window would deliver for a key, the character only comes with a press
+fn press(app: &mut Application, key_code: VirtualKeyCode, character: char) {
+    let input = key(ElementState::Pressed, key_code);
+    app.world
+        .get_resource_mut::<InputConsumer>()
+        .unwrap()
+        .capture_keyboard_input(&input);
+    let mut text_input = app.world.get_resource_mut::<TextInput>().unwrap();
+    text_input.capture_keyboard_input(&input);
+    text_input.capture_character(character);
+}
+
+fn release(app: &mut Application, key_code: VirtualKeyCode) {
+    let input = key(ElementState::Released, key_code);
+    app.world
+        .get_resource_mut::<InputConsumer>()
+        .unwrap()
+        .capture_keyboard_input(&input);
+}
+
+fn text_input(app: &mut Application) -> RefMut<'_, TextInput> {
+    app.world.get_resource_mut::<TextInput>().unwrap()
+}
+
+#[test]
+fn focused_field_swallows_the_keyboard() {
+    let mut app = Application::headless(World::new());
+    // Moves with WASD like the KeyboardHandler, which needs a window
+    let (mover, moved) = probe("Mover", RunStage::Update, |world| {
+        let input_map = world.get_resource::<InputMap>().unwrap();
+        let input = world.get_resource::<InputConsumer>().unwrap();
+        input_map.axis(&input, "move_forward")
+    });
+    let (console, typed) = probe("Console", RunStage::Update, |world| {
+        world.get_resource::<TextInput>().unwrap().text()
+    });
+    app.add_system(mover.run_if(not_typing));
+    app.add_system(console);
+
+    text_input(&mut app).take_focus("console");
+    press(&mut app, VirtualKeyCode::W, 'w');
+    assert_eq!(app.run_frame().unwrap(), None);
+    assert_eq!(last_seen(&typed), "w");
+    assert!(moved.borrow().is_empty());
+
+    // Escape is for the field, QuitOnAction doesn't get to see it
+    press(&mut app, VirtualKeyCode::Escape, '\u{1b}');
+    assert_eq!(app.run_frame().unwrap(), None);
+    assert_eq!(last_seen(&typed), "");
+    assert!(moved.borrow().is_empty());
+    release(&mut app, VirtualKeyCode::Escape);
+
+    // W is still held, released by the console the keyboard moves again
+    text_input(&mut app).release_focus("console");
+    assert_eq!(app.run_frame().unwrap(), None);
+    assert_eq!(*moved.borrow(), vec![1.0]);
+    assert_eq!(last_seen(&typed), "");
+
+    press(&mut app, VirtualKeyCode::Escape, '\u{1b}');
+    assert_eq!(app.run_frame().unwrap(), Some(ExitStatus::Success));
+}